@@ -0,0 +1,53 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+// the same generated function as the large input CLI test
+const FUNCTION: &str =
+    "int main(void) {\n    return (1 + 2) * (4 - 3) / (3 % 2) << ~(-5) && 7 >= 6;\n}\n";
+const RUNS: usize = 5;
+
+/**
+ * The fastest of a few runs of `compiler --lex` over `path`, the others are
+ * slowed down by whatever else the machine is doing
+ */
+fn time_lex(path: &Path) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            let output = Command::new(env!("CARGO_BIN_EXE_compiler"))
+                .arg("--lex")
+                .arg(path)
+                .output()
+                .expect("compiler runs");
+            let elapsed = start.elapsed();
+            assert!(
+                output.status.success(),
+                "{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            elapsed
+        })
+        .min()
+        .expect("at least one run")
+}
+
+/**
+ * Lexes generated sources that double in size, up to a few hundred KB. A
+ * linear lexer keeps the time per KB about the same at every size, the old
+ * regex/drain lexer was quadratic and doubled it every time
+ */
+fn main() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    println!("{:>10} {:>12} {:>12}", "bytes", "time", "per KB");
+    for copies in [250, 500, 1000, 2000, 4000, 8000] {
+        let src = FUNCTION.repeat(copies);
+        let path = dir.join(format!("lexer_bench_{copies}.i"));
+        fs::write(&path, &src).expect("bench input can be written");
+        let elapsed = time_lex(&path);
+        fs::remove_file(&path).expect("bench input can be removed");
+        let per_kb = elapsed.div_f64(src.len() as f64 / 1024.0);
+        println!("{:>10} {:>12.2?} {:>12.2?}", src.len(), elapsed, per_kb);
+    }
+}
@@ -1,5 +1,5 @@
-LAST_COMPLETED_CHAPTER=4
-CHAPTER_IN_PROGRESS=5
+LAST_COMPLETED_CHAPTER=8
+CHAPTER_IN_PROGRESS=8
 STAGE_IN_PROGRESS="parse"
 
 echo "Running full test suite from chapter $LAST_COMPLETED_CHAPTER..."
@@ -3,7 +3,7 @@ use std::cmp::max;
 
 fn operand_stack_offset(operand: &asm::Operand) -> i32 {
     match operand {
-        asm::Operand::Stack(offset) => offset.clone(),
+        asm::Operand::Stack(offset) => *offset,
         _ => -1,
     }
 }
@@ -32,7 +32,7 @@ fn get_instruction_max_stack_offset(instruction: &asm::Instruction) -> i32 {
 }
 
 pub fn add_stack_allocation_instruction(
-    instructions: &Vec<asm::Instruction>,
+    instructions: &[asm::Instruction],
 ) -> Vec<asm::Instruction> {
     let mut stack_appetite = 0;
     for instruction in instructions {
@@ -41,6 +41,6 @@ pub fn add_stack_allocation_instruction(
     }
 
     let mut result_instructions = vec![asm::Instruction::AllocateStack(stack_appetite)];
-    result_instructions.extend(instructions.clone());
+    result_instructions.extend_from_slice(instructions);
     result_instructions
 }
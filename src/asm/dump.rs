@@ -0,0 +1,149 @@
+use super::ast::*;
+use crate::core::dump::{Dump, Node};
+use crate::core::source_map::SourceMap;
+
+impl Dump for Register {
+    fn dump(&self, _source_map: &SourceMap) -> Node {
+        Node::Unit(match self {
+            Register::AX => "AX",
+            Register::DX => "DX",
+            Register::R10 => "R10",
+            Register::R11 => "R11",
+            Register::CL => "CL",
+            Register::CX => "CX",
+        })
+    }
+}
+
+impl Dump for CondCode {
+    fn dump(&self, _source_map: &SourceMap) -> Node {
+        Node::Unit(match self {
+            CondCode::E => "E",
+            CondCode::NE => "NE",
+            CondCode::G => "G",
+            CondCode::GE => "GE",
+            CondCode::L => "L",
+            CondCode::LE => "LE",
+        })
+    }
+}
+
+impl Dump for UnaryOperator {
+    fn dump(&self, _source_map: &SourceMap) -> Node {
+        Node::Unit(match self {
+            UnaryOperator::Neg => "Neg",
+            UnaryOperator::Not => "Not",
+        })
+    }
+}
+
+impl Dump for BinaryOperator {
+    fn dump(&self, _source_map: &SourceMap) -> Node {
+        Node::Unit(match self {
+            BinaryOperator::Add => "Add",
+            BinaryOperator::Sub => "Sub",
+            BinaryOperator::Mul => "Mul",
+            BinaryOperator::Sar => "Sar",
+            BinaryOperator::Sal => "Sal",
+            BinaryOperator::And => "And",
+            BinaryOperator::Or => "Or",
+            BinaryOperator::Xor => "Xor",
+        })
+    }
+}
+
+impl Dump for Operand {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        match self {
+            Operand::Immediate(value) => {
+                Node::Variant("Immediate", vec![("value", value.dump(source_map))])
+            }
+            Operand::Register(register) => {
+                Node::Variant("Register", vec![("register", register.dump(source_map))])
+            }
+            Operand::Pseudo(name) => Node::Variant("Pseudo", vec![("name", name.dump(source_map))]),
+            Operand::Stack(offset) => {
+                Node::Variant("Stack", vec![("offset", offset.dump(source_map))])
+            }
+        }
+    }
+}
+
+impl Dump for Instruction {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        match self {
+            Instruction::UnaryOp(operator, operand) => Node::Variant(
+                "UnaryOp",
+                vec![
+                    ("operator", operator.dump(source_map)),
+                    ("operand", operand.dump(source_map)),
+                ],
+            ),
+            Instruction::Binary(operator, src, dst) => Node::Variant(
+                "Binary",
+                vec![
+                    ("operator", operator.dump(source_map)),
+                    ("src", src.dump(source_map)),
+                    ("dst", dst.dump(source_map)),
+                ],
+            ),
+            Instruction::Mov(src, dst) => Node::Variant(
+                "Mov",
+                vec![("src", src.dump(source_map)), ("dst", dst.dump(source_map))],
+            ),
+            Instruction::AllocateStack(bytes) => {
+                Node::Variant("AllocateStack", vec![("bytes", bytes.dump(source_map))])
+            }
+            Instruction::Idiv(operand) => {
+                Node::Variant("Idiv", vec![("operand", operand.dump(source_map))])
+            }
+            Instruction::Cdq => Node::Unit("Cdq"),
+            Instruction::Ret => Node::Unit("Ret"),
+            Instruction::Label(name) => {
+                Node::Variant("Label", vec![("name", name.dump(source_map))])
+            }
+            Instruction::Jmp(target) => {
+                Node::Variant("Jmp", vec![("target", target.dump(source_map))])
+            }
+            // AT&T order, `cmp src, dst` compares dst with src
+            Instruction::Cmp(src, dst) => Node::Variant(
+                "Cmp",
+                vec![("src", src.dump(source_map)), ("dst", dst.dump(source_map))],
+            ),
+            Instruction::JmpCC(condition, target) => Node::Variant(
+                "JmpCC",
+                vec![
+                    ("condition", condition.dump(source_map)),
+                    ("target", target.dump(source_map)),
+                ],
+            ),
+            Instruction::SetCC(condition, dst) => Node::Variant(
+                "SetCC",
+                vec![
+                    ("condition", condition.dump(source_map)),
+                    ("dst", dst.dump(source_map)),
+                ],
+            ),
+        }
+    }
+}
+
+impl Dump for Function {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        let Function::Function(name, instructions) = self;
+        Node::Variant(
+            "Function",
+            vec![
+                ("name", name.dump(source_map)),
+                ("instructions", instructions.dump(source_map)),
+            ],
+        )
+    }
+}
+
+impl Dump for Program {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        let Program::Program(function) = self;
+        Node::Variant("Program", vec![("function", function.dump(source_map))])
+    }
+}
@@ -108,11 +108,11 @@ fn use_scratch_register_for_cmp(op1: asm::Operand, op2: asm::Operand) -> Vec<asm
 }
 
 pub fn use_scratch_registers(
-    instructions: &Vec<asm::Instruction>,
+    instructions: &[asm::Instruction],
 ) -> Vec<asm::Instruction> {
     let mut result_instructions: Vec<asm::Instruction> = vec![];
 
-    for instruction in instructions.clone() {
+    for instruction in instructions.iter().cloned() {
         match instruction {
             asm::Instruction::Mov(src, dst) => {
                 result_instructions.extend(use_scratch_registers_for_mov(src, dst))
@@ -152,7 +152,7 @@ fn translate_function(func: tacky::Function) -> asm::Function {
         tacky::Function::Function(name, instructions) => {
             let asm_instructions: Vec<asm::Instruction> = instructions
                 .into_iter()
-                .flat_map(|inst| translate_instruction(inst))
+                .flat_map(translate_instruction)
                 .collect();
 
             asm::Function::Function(name, asm_instructions)
@@ -1,5 +1,5 @@
 use super::ast as asm;
-type Middleware = fn(&Vec<asm::Instruction>) -> Vec<asm::Instruction>;
+type Middleware = fn(&[asm::Instruction]) -> Vec<asm::Instruction>;
 
 pub fn run_asm_middleware(program: asm::Program, middleware: Vec<Middleware>) -> asm::Program {
     let asm::Program::Program(asm::Function::Function(ref name, ref instructions)) = program;
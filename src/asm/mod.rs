@@ -1,5 +1,7 @@
 mod add_stack_allocation;
 pub mod ast;
+#[cfg(feature = "dump-ast")]
+mod dump;
 mod fix_instruction_operands;
 mod from_tacky;
 mod middleware;
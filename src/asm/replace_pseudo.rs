@@ -2,7 +2,7 @@ use super::ast as asm;
 use std::collections::HashMap;
 
 pub fn replace_pseudoregisters_in_instructions(
-    instructions: &Vec<asm::Instruction>,
+    instructions: &[asm::Instruction],
 ) -> Vec<asm::Instruction> {
     let mut instructions_without_pseudo: Vec<asm::Instruction> = vec![];
     let mut stack_offset_table: HashMap<String, i32> = HashMap::new();
@@ -15,7 +15,7 @@ pub fn replace_pseudoregisters_in_instructions(
                 return asm::Operand::Stack(*offset);
             }
 
-            curr_offset = curr_offset + 4;
+            curr_offset += 4;
             stack_offset_table.insert(name.clone(), curr_offset);
             return asm::Operand::Stack(curr_offset);
         }
@@ -23,7 +23,7 @@ pub fn replace_pseudoregisters_in_instructions(
         operand
     };
 
-    for instruction in instructions.clone() {
+    for instruction in instructions.iter().cloned() {
         let instruction_without_pseudo = match instruction {
             asm::Instruction::Mov(src, dst) => {
                 asm::Instruction::Mov(replace_pseudoregister(src), replace_pseudoregister(dst))
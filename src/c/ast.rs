@@ -1,3 +1,5 @@
+use crate::core::span::Span;
+
 #[derive(PartialEq, Debug)]
 pub enum UnaryOperator {
     Negation,
@@ -52,31 +54,90 @@ pub fn binary_operator_precedence(operator: &BinaryOperator) -> i32 {
     }
 }
 
+// `?:` binds tighter than assignment and looser than every other operator
+pub const CONDITIONAL_PRECEDENCE: i32 = 3;
+
 #[derive(PartialEq, Debug)]
 pub enum Expression {
-    Constant(i32),
-    Var(String),
-    Unary(UnaryOperator, Box<Expression>),
-    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
-    Assignment(Box<Expression>, Box<Expression>),
+    Constant(i32, Span),
+    CharLiteral(u8, Span),
+    StringLiteral(Vec<u8>, Span),
+    Var(String, Span),
+    Unary(UnaryOperator, Box<Expression>, Span),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>, Span),
+    Assignment(Box<Expression>, Box<Expression>, Span),
+    Conditional(Box<Expression>, Box<Expression>, Box<Expression>, Span),
+}
+
+impl Expression {
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Constant(_, span) => *span,
+            Expression::CharLiteral(_, span) => *span,
+            Expression::StringLiteral(_, span) => *span,
+            Expression::Var(_, span) => *span,
+            Expression::Unary(_, _, span) => *span,
+            Expression::Binary(_, _, _, span) => *span,
+            Expression::Assignment(_, _, span) => *span,
+            Expression::Conditional(_, _, _, span) => *span,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum ForInit {
+    Declaration(String, Option<Expression>, Span),
+    Expression(Option<Expression>),
+}
+
+/**
+ * The labels `label_loops` gives a switch: its own, which `break` jumps past,
+ * and those of its cases, with their values, and of its default
+ */
+#[derive(PartialEq, Debug)]
+pub struct SwitchLabels {
+    pub label: String,
+    pub cases: Vec<(i32, String)>,
+    pub default: Option<String>,
 }
 
+/**
+ * Loops, switches and the statements jumping around in them carry labels,
+ * which are `None` until `label_loops` has run
+ */
 #[derive(PartialEq, Debug)]
 pub enum Statement {
-    Return(Expression),
-    Expression(Expression),
-    Null,
+    Return(Expression, Span),
+    Expression(Expression, Span),
+    If(Expression, Box<Statement>, Option<Box<Statement>>, Span),
+    Compound(Vec<Block>, Span),
+    Break(Option<String>, Span),
+    Continue(Option<String>, Span),
+    While(Expression, Box<Statement>, Option<String>, Span),
+    DoWhile(Box<Statement>, Expression, Option<String>, Span),
+    For(
+        Box<ForInit>,
+        Option<Expression>,
+        Option<Expression>,
+        Box<Statement>,
+        Option<String>,
+        Span,
+    ),
+    Switch(Expression, Box<Statement>, Option<SwitchLabels>, Span),
+    Case(Expression, Box<Statement>, Option<String>, Span),
+    Default(Box<Statement>, Option<String>, Span),
+    Null(Span),
 }
 
 #[derive(PartialEq, Debug)]
 pub enum Block {
     Statement(Statement),
-    Declaration(String, Option<Expression>),
+    Declaration(String, Option<Expression>, Span),
 }
 
 #[derive(PartialEq, Debug)]
 pub enum Function {
-    Function(String, Vec<Block>),
+    Function(String, Vec<Block>, Span),
 }
 
 #[derive(PartialEq, Debug)]
@@ -0,0 +1,299 @@
+use super::ast::*;
+use crate::core::dump::{Dump, Node};
+use crate::core::source_map::SourceMap;
+
+impl Dump for UnaryOperator {
+    fn dump(&self, _source_map: &SourceMap) -> Node {
+        Node::Unit(match self {
+            UnaryOperator::Negation => "Negation",
+            UnaryOperator::Complement => "Complement",
+            UnaryOperator::Not => "Not",
+        })
+    }
+}
+
+impl Dump for BinaryOperator {
+    fn dump(&self, _source_map: &SourceMap) -> Node {
+        Node::Unit(match self {
+            BinaryOperator::Add => "Add",
+            BinaryOperator::Subtract => "Subtract",
+            BinaryOperator::Multiply => "Multiply",
+            BinaryOperator::Divide => "Divide",
+            BinaryOperator::Modulo => "Modulo",
+            BinaryOperator::LeftShift => "LeftShift",
+            BinaryOperator::RightShift => "RightShift",
+            BinaryOperator::BitwiseAnd => "BitwiseAnd",
+            BinaryOperator::BitwiseXor => "BitwiseXor",
+            BinaryOperator::BitwiseOr => "BitwiseOr",
+            BinaryOperator::LogicalAnd => "LogicalAnd",
+            BinaryOperator::LogicalOr => "LogicalOr",
+            BinaryOperator::IsEqual => "IsEqual",
+            BinaryOperator::NotEqual => "NotEqual",
+            BinaryOperator::LessThan => "LessThan",
+            BinaryOperator::LessThanOrEqual => "LessThanOrEqual",
+            BinaryOperator::GreaterThan => "GreaterThan",
+            BinaryOperator::GreaterThanOrEqual => "GreaterThanOrEqual",
+            BinaryOperator::Equal => "Equal",
+        })
+    }
+}
+
+impl Dump for Expression {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        match self {
+            Expression::Constant(value, span) => Node::Variant(
+                "Constant",
+                vec![
+                    ("value", value.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Expression::CharLiteral(value, span) => Node::Variant(
+                "CharLiteral",
+                vec![
+                    ("value", value.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Expression::StringLiteral(bytes, span) => Node::Variant(
+                "StringLiteral",
+                vec![
+                    ("bytes", bytes.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Expression::Var(name, span) => Node::Variant(
+                "Var",
+                vec![
+                    ("name", name.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Expression::Unary(operator, operand, span) => Node::Variant(
+                "Unary",
+                vec![
+                    ("operator", operator.dump(source_map)),
+                    ("operand", operand.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Expression::Binary(operator, left, right, span) => Node::Variant(
+                "Binary",
+                vec![
+                    ("operator", operator.dump(source_map)),
+                    ("left", left.dump(source_map)),
+                    ("right", right.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Expression::Assignment(lvalue, value, span) => Node::Variant(
+                "Assignment",
+                vec![
+                    ("lvalue", lvalue.dump(source_map)),
+                    ("value", value.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Expression::Conditional(condition, then, otherwise, span) => Node::Variant(
+                "Conditional",
+                vec![
+                    ("condition", condition.dump(source_map)),
+                    ("then", then.dump(source_map)),
+                    ("otherwise", otherwise.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+        }
+    }
+}
+
+impl Dump for ForInit {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        match self {
+            ForInit::Declaration(name, initializer, span) => Node::Variant(
+                "Declaration",
+                vec![
+                    ("name", name.dump(source_map)),
+                    ("initializer", initializer.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            ForInit::Expression(expression) => Node::Variant(
+                "Expression",
+                vec![("expression", expression.dump(source_map))],
+            ),
+        }
+    }
+}
+
+impl Dump for SwitchLabels {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        let cases = self
+            .cases
+            .iter()
+            .map(|(value, label)| {
+                Node::Struct(
+                    "Case",
+                    vec![
+                        ("value", value.dump(source_map)),
+                        ("label", label.dump(source_map)),
+                    ],
+                )
+            })
+            .collect();
+        Node::Struct(
+            "SwitchLabels",
+            vec![
+                ("label", self.label.dump(source_map)),
+                ("cases", Node::List(cases)),
+                ("default", self.default.dump(source_map)),
+            ],
+        )
+    }
+}
+
+impl Dump for Statement {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        match self {
+            Statement::Return(expression, span) => Node::Variant(
+                "Return",
+                vec![
+                    ("expression", expression.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Statement::Expression(expression, span) => Node::Variant(
+                "Expression",
+                vec![
+                    ("expression", expression.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Statement::If(condition, then, otherwise, span) => Node::Variant(
+                "If",
+                vec![
+                    ("condition", condition.dump(source_map)),
+                    ("then", then.dump(source_map)),
+                    ("otherwise", otherwise.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Statement::Compound(blocks, span) => Node::Variant(
+                "Compound",
+                vec![
+                    ("blocks", blocks.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Statement::Break(label, span) => Node::Variant(
+                "Break",
+                vec![
+                    ("label", label.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Statement::Continue(label, span) => Node::Variant(
+                "Continue",
+                vec![
+                    ("label", label.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Statement::While(condition, body, label, span) => Node::Variant(
+                "While",
+                vec![
+                    ("condition", condition.dump(source_map)),
+                    ("body", body.dump(source_map)),
+                    ("label", label.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Statement::DoWhile(body, condition, label, span) => Node::Variant(
+                "DoWhile",
+                vec![
+                    ("body", body.dump(source_map)),
+                    ("condition", condition.dump(source_map)),
+                    ("label", label.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Statement::For(init, condition, post, body, label, span) => Node::Variant(
+                "For",
+                vec![
+                    ("init", init.dump(source_map)),
+                    ("condition", condition.dump(source_map)),
+                    ("post", post.dump(source_map)),
+                    ("body", body.dump(source_map)),
+                    ("label", label.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Statement::Switch(control, body, labels, span) => Node::Variant(
+                "Switch",
+                vec![
+                    ("control", control.dump(source_map)),
+                    ("body", body.dump(source_map)),
+                    ("labels", labels.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Statement::Case(value, body, label, span) => Node::Variant(
+                "Case",
+                vec![
+                    ("value", value.dump(source_map)),
+                    ("body", body.dump(source_map)),
+                    ("label", label.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Statement::Default(body, label, span) => Node::Variant(
+                "Default",
+                vec![
+                    ("body", body.dump(source_map)),
+                    ("label", label.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+            Statement::Null(span) => Node::Variant("Null", vec![("span", span.dump(source_map))]),
+        }
+    }
+}
+
+impl Dump for Block {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        match self {
+            Block::Statement(statement) => {
+                Node::Variant("Statement", vec![("statement", statement.dump(source_map))])
+            }
+            Block::Declaration(name, initializer, span) => Node::Variant(
+                "Declaration",
+                vec![
+                    ("name", name.dump(source_map)),
+                    ("initializer", initializer.dump(source_map)),
+                    ("span", span.dump(source_map)),
+                ],
+            ),
+        }
+    }
+}
+
+impl Dump for Function {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        let Function::Function(name, body, span) = self;
+        Node::Variant(
+            "Function",
+            vec![
+                ("name", name.dump(source_map)),
+                ("body", body.dump(source_map)),
+                ("span", span.dump(source_map)),
+            ],
+        )
+    }
+}
+
+impl Dump for Program {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        let Program::Program(function) = self;
+        Node::Variant("Program", vec![("function", function.dump(source_map))])
+    }
+}
@@ -1,29 +1,53 @@
 use super::ast::*;
 use super::lexer::*;
+use crate::core::diagnostic::Diagnostic;
 use crate::core::parser::Parser;
+use crate::core::span::Span;
 
-macro_rules! eat_token_of_kind {
-    ($parser:expr, $expected:pat) => {{
-        let tok = $parser.eat().expect(&format!(
-            "Expected {:?} but found None",
-            stringify!($expected)
-        ));
-        match &tok {
-            $expected => tok,
-            _ => panic!("Expected {:?} but found {:?}", stringify!($expected), tok),
-        }
-    }};
+type ParseResult<T> = Result<T, Diagnostic>;
+
+/**
+ * Consumes the next token, which must be `expected`. `context` completes the
+ * error message, e.g. "expected ';' after return statement"
+ */
+fn expect_token(parser: &mut Parser<Token>, expected: Token, context: &str) -> ParseResult<Token> {
+    parser
+        .expect(|tok| *tok == expected, &format!("'{expected}'"))
+        .map_err(|error| {
+            Diagnostic::error(format!("expected {} {context}", error.expected), error.span)
+        })
 }
 
-macro_rules! eat_known_token {
-    ($parser:expr, $expected:expr) => {
-        let tok = $parser
-            .eat()
-            .expect(&format!("Expected {:?} but found None", $expected));
-        if (tok != $expected) {
-            panic!("Expected {:?} but found {:?}", $expected, tok)
-        }
-    };
+/**
+ * Describes the next token for "expected X before Y" style messages
+ */
+fn describe_next(parser: &Parser<Token>) -> String {
+    match parser.peek() {
+        Some(tok) => format!("'{tok}'"),
+        None => String::from("end of input"),
+    }
+}
+
+/**
+ * Consumes the token closing a pair, e.g. the `)` of a `(`. The error points
+ * back at the opening token as well
+ */
+fn expect_closing(
+    parser: &mut Parser<Token>,
+    closing: Token,
+    opening: Token,
+    opening_span: Span,
+) -> ParseResult<Token> {
+    if parser.peek() != Some(&closing) {
+        return Err(Diagnostic::error(
+            format!("expected '{closing}' before {}", describe_next(parser)),
+            parser.peek_span(),
+        )
+        .with_note(format!("to match this '{opening}'"), opening_span));
+    }
+    Ok(parser
+        .eat()
+        .expect("next token was checked to be the closing one"))
 }
 
 fn translate_tok_to_unop(tok: &Token) -> Option<UnaryOperator> {
@@ -60,209 +84,931 @@ fn translate_tok_to_binop(tok: &Token) -> Option<BinaryOperator> {
     }
 }
 
-fn parse_constant(parser: &mut Parser<Token>) -> Expression {
-    let tok = eat_token_of_kind!(parser, Token::Constant(_));
-    match tok {
-        Token::Constant(val) => {
-            let i32_val = val
-                .parse::<i32>()
-                .expect(&format!("{val} should be an integer"));
-            Ok(Expression::Constant(i32_val))
+fn parse_constant(parser: &mut Parser<Token>) -> ParseResult<Expression> {
+    let span = parser.peek_span();
+    match parser.eat() {
+        // only int is supported by later stages for now
+        Some(Token::Constant(val, IntegerType::Int)) => Ok(Expression::Constant(val as i32, span)),
+        Some(Token::Constant(val, ty)) => Err(Diagnostic::error(
+            format!("{val} has type {ty} but only int constants are supported"),
+            span,
+        )),
+        _ => Err(Diagnostic::error("expected a constant", span)),
+    }
+}
+
+// adjacent string literals are concatenated into one, `"a" "b"` is `"ab"`
+fn parse_string_literal(parser: &mut Parser<Token>) -> Expression {
+    let start = parser.peek_span();
+    let mut bytes: Vec<u8> = vec![];
+    while let Some(Token::StringLiteral(_)) = parser.peek() {
+        if let Some(Token::StringLiteral(literal)) = parser.eat() {
+            bytes.extend(literal);
         }
-        _ => Err(format!("{:?} should be a constant", tok)),
     }
-    .unwrap()
+    Expression::StringLiteral(bytes, start.to(parser.last_span()))
 }
 
-fn parse_primary(parser: &mut Parser<Token>) -> Expression {
-    let next_tok = parser
-        .peek()
-        .expect("Expected expression but no token found");
+fn parse_primary(parser: &mut Parser<Token>) -> ParseResult<Expression> {
+    let start = parser.peek_span();
+    let Some(next_tok) = parser.peek() else {
+        return Err(Diagnostic::error(
+            "expected expression before end of input",
+            start,
+        ));
+    };
     match next_tok {
-        Token::Constant(_) => parse_constant(parser),
+        Token::Constant(_, _) => parse_constant(parser),
+        Token::CharLiteral(value) => {
+            let value = *value;
+            parser.eat();
+            Ok(Expression::CharLiteral(value, start))
+        }
+        Token::StringLiteral(_) => Ok(parse_string_literal(parser)),
         Token::OpenParenthesis => {
-            eat_known_token!(parser, Token::OpenParenthesis);
-            let expr = parse_expression(parser);
-            eat_known_token!(parser, Token::CloseParenthesis);
-            expr
+            parser.eat();
+            let expr = parse_expression(parser)?;
+            expect_closing(
+                parser,
+                Token::CloseParenthesis,
+                Token::OpenParenthesis,
+                start,
+            )?;
+            Ok(expr)
         }
         Token::Identifier(name) => {
             let name = name.clone();
-            eat_token_of_kind!(parser, Token::Identifier(_));
-            Expression::Var(name)
+            parser.eat();
+            Ok(Expression::Var(name, start))
         }
-        _ => {
-            let tok = parser
-                .eat()
-                .expect("Expected start of expression but found None.");
-            let maybe_unop = translate_tok_to_unop(&tok);
-            let Some(unop) = maybe_unop else {
-                panic!("Invalid expression. Cannot begin with {:?}", tok);
+        tok => {
+            let Some(unop) = translate_tok_to_unop(tok) else {
+                return Err(Diagnostic::error(
+                    format!("expected expression before '{tok}'"),
+                    start,
+                ));
             };
-            let expr = parse_primary(parser);
-            return Expression::Unary(unop, Box::new(expr));
+            parser.eat();
+            let expr = parse_primary(parser)?;
+            let span = start.to(expr.span());
+            Ok(Expression::Unary(unop, Box::new(expr), span))
         }
     }
 }
 
-fn is_next_token_binary_op_no_lower_precedence(
-    parser: &mut Parser<Token>,
-    min_precedence: i32,
-) -> bool {
-    let tok = parser.peek().expect("Expected a token but found None");
-    let binop = translate_tok_to_binop(&tok);
-    if let Some(binop_val) = binop {
-        return binary_operator_precedence(&binop_val) >= min_precedence;
+// precedence of the binary or conditional operator coming next, if any
+fn next_operator_precedence(parser: &mut Parser<Token>) -> Option<i32> {
+    match parser.peek() {
+        Some(Token::QuestionMark) => Some(CONDITIONAL_PRECEDENCE),
+        tok => tok
+            .and_then(translate_tok_to_binop)
+            .map(|binop| binary_operator_precedence(&binop)),
     }
-    return false;
 }
 
-fn parse_expression_with_precedence(parser: &mut Parser<Token>, min_precedence: i32) -> Expression {
-    let mut expr = parse_primary(parser);
-    while is_next_token_binary_op_no_lower_precedence(parser, min_precedence) {
-        let tok = parser
+fn parse_expression_with_precedence(
+    parser: &mut Parser<Token>,
+    min_precedence: i32,
+) -> ParseResult<Expression> {
+    let mut expr = parse_primary(parser)?;
+    while let Some(precedence) = next_operator_precedence(parser)
+        && precedence >= min_precedence
+    {
+        if parser.peek() == Some(&Token::QuestionMark) {
+            let question_mark = parser.peek_span();
+            parser.eat();
+            // anything can go between `?` and `:`, like inside parentheses
+            let then = parse_expression(parser)?;
+            expect_closing(parser, Token::Colon, Token::QuestionMark, question_mark)?;
+            // right associative, `a ? b : c ? d : e` is `a ? b : (c ? d : e)`
+            let otherwise = parse_expression_with_precedence(parser, precedence)?;
+            let span = expr.span().to(otherwise.span());
+            expr =
+                Expression::Conditional(Box::new(expr), Box::new(then), Box::new(otherwise), span);
+            continue;
+        }
+        let operator = parser
             .eat()
-            .expect("Expected operator in expression but found None.");
-        let operator = translate_tok_to_binop(&tok)
-            .expect(format!("Expected binary operator but found {:?}", tok).as_str());
-        let rhs =
-            parse_expression_with_precedence(parser, binary_operator_precedence(&operator) + 1);
+            .as_ref()
+            .and_then(translate_tok_to_binop)
+            .expect("next token was checked to be a binary operator");
+        // assignment is right associative, `a = b = c` is `a = (b = c)`
+        let rhs_precedence = match operator {
+            BinaryOperator::Equal => precedence,
+            _ => precedence + 1,
+        };
+        let rhs = parse_expression_with_precedence(parser, rhs_precedence)?;
+        let span = expr.span().to(rhs.span());
         expr = match operator {
-            BinaryOperator::Equal => Expression::Assignment(Box::new(expr), Box::new(rhs)),
-            _ => Expression::Binary(operator, Box::new(expr), Box::new(rhs)),
+            BinaryOperator::Equal => Expression::Assignment(Box::new(expr), Box::new(rhs), span),
+            _ => Expression::Binary(operator, Box::new(expr), Box::new(rhs), span),
         };
     }
-    expr
+    Ok(expr)
 }
 
-fn parse_expression(parser: &mut Parser<Token>) -> Expression {
+fn parse_expression(parser: &mut Parser<Token>) -> ParseResult<Expression> {
     parse_expression_with_precedence(parser, 0)
 }
 
-// parse Block::Statement. We currently support 3 types of statements
+// the `(<expr>)` after `if`, `while` and the like
+fn parse_parenthesized_condition(
+    parser: &mut Parser<Token>,
+    keyword: &str,
+) -> ParseResult<Expression> {
+    let open = parser.peek_span();
+    expect_token(
+        parser,
+        Token::OpenParenthesis,
+        &format!("after '{keyword}'"),
+    )?;
+    let condition = parse_expression(parser)?;
+    expect_closing(
+        parser,
+        Token::CloseParenthesis,
+        Token::OpenParenthesis,
+        open,
+    )?;
+    Ok(condition)
+}
+
+// an expression that may be left out before a `;`, as in `for (;;)`
+fn parse_optional_expression(parser: &mut Parser<Token>) -> ParseResult<Option<Expression>> {
+    match parser.peek() {
+        Some(Token::Semicolon) => Ok(None),
+        _ => Ok(Some(parse_expression(parser)?)),
+    }
+}
+
+// the first clause of a `for`, including its `;`
+fn parse_for_init(parser: &mut Parser<Token>) -> ParseResult<ForInit> {
+    if let Some(Token::Keyword(Keyword::Int)) = parser.peek() {
+        let Block::Declaration(name, initializer, span) = parse_declaration(parser)? else {
+            unreachable!("parse_declaration only returns declarations");
+        };
+        return Ok(ForInit::Declaration(name, initializer, span));
+    }
+    let init = parse_optional_expression(parser)?;
+    expect_token(parser, Token::Semicolon, "after initializer of 'for'")?;
+    Ok(ForInit::Expression(init))
+}
+
+// the init, condition and post of a `for` after its `(`, up to and including
+// the `)` matching the `(` at `open`
+fn parse_for_header(
+    parser: &mut Parser<Token>,
+    open: Span,
+) -> ParseResult<(ForInit, Option<Expression>, Option<Expression>)> {
+    let init = parse_for_init(parser)?;
+    let condition = parse_optional_expression(parser)?;
+    expect_token(parser, Token::Semicolon, "after condition of 'for'")?;
+    let post = match parser.peek() {
+        Some(Token::CloseParenthesis) => None,
+        _ => Some(parse_expression(parser)?),
+    };
+    expect_closing(
+        parser,
+        Token::CloseParenthesis,
+        Token::OpenParenthesis,
+        open,
+    )?;
+    Ok((init, condition, post))
+}
+
+// parse a statement. We currently support 7 kinds of statements
 // 1. Null statements defined by a single semicolon
 // 2. Expressions defined as <expr>;
 // 3. Return statements defined as return <expr>;
-fn parse_statement(parser: &mut Parser<Token>) -> Block {
+// 4. If statements defined as if (<expr>) <statement> [else <statement>]
+// 5. Compound statements defined as { <block>* }
+// 6. Loops, `while`, `do ... while` and `for`, and `break`/`continue` in them
+// 7. Switch statements and the `case`/`default` labelled statements in them
+fn parse_statement(
+    parser: &mut Parser<Token>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> ParseResult<Statement> {
+    let start = parser.peek_span();
     match parser.peek() {
         Some(&Token::Semicolon) => {
-            eat_known_token!(parser, Token::Semicolon);
-            Block::Statement(Statement::Null)
+            parser.eat();
+            Ok(Statement::Null(start))
+        }
+        Some(Token::Keyword(Keyword::Return)) => {
+            parser.eat();
+            let expr = parse_expression(parser)?;
+            expect_token(parser, Token::Semicolon, "after return statement")?;
+            Ok(Statement::Return(expr, start.to(parser.last_span())))
         }
-        Some(Token::Keyword(key)) if key == "return" => {
-            eat_known_token!(parser, Token::Keyword(String::from("return")));
-            let expr = parse_expression(parser);
-            eat_known_token!(parser, Token::Semicolon);
-            Block::Statement(Statement::Return(expr))
+        Some(Token::Keyword(Keyword::If)) => {
+            parser.eat();
+            let condition = parse_parenthesized_condition(parser, "if")?;
+            let then = parse_statement(parser, diagnostics)?;
+            // a dangling `else` belongs to the innermost `if`, which is the
+            // one parsing it here
+            let otherwise = match parser.peek() {
+                Some(Token::Keyword(Keyword::Else)) => {
+                    parser.eat();
+                    Some(Box::new(parse_statement(parser, diagnostics)?))
+                }
+                _ => None,
+            };
+            Ok(Statement::If(
+                condition,
+                Box::new(then),
+                otherwise,
+                start.to(parser.last_span()),
+            ))
+        }
+        Some(Token::Keyword(Keyword::Break)) => {
+            parser.eat();
+            expect_token(parser, Token::Semicolon, "after 'break'")?;
+            Ok(Statement::Break(None, start.to(parser.last_span())))
+        }
+        Some(Token::Keyword(Keyword::Continue)) => {
+            parser.eat();
+            expect_token(parser, Token::Semicolon, "after 'continue'")?;
+            Ok(Statement::Continue(None, start.to(parser.last_span())))
+        }
+        Some(Token::Keyword(Keyword::While)) => {
+            parser.eat();
+            let condition = parse_parenthesized_condition(parser, "while")?;
+            let body = parse_statement(parser, diagnostics)?;
+            Ok(Statement::While(
+                condition,
+                Box::new(body),
+                None,
+                start.to(parser.last_span()),
+            ))
+        }
+        Some(Token::Keyword(Keyword::Do)) => {
+            parser.eat();
+            let body = parse_statement(parser, diagnostics)?;
+            expect_token(parser, Token::Keyword(Keyword::While), "after body of 'do'")?;
+            let condition = parse_parenthesized_condition(parser, "while")?;
+            expect_token(parser, Token::Semicolon, "after 'do' statement")?;
+            Ok(Statement::DoWhile(
+                Box::new(body),
+                condition,
+                None,
+                start.to(parser.last_span()),
+            ))
+        }
+        Some(Token::Keyword(Keyword::For)) => {
+            parser.eat();
+            let open = parser.peek_span();
+            expect_token(parser, Token::OpenParenthesis, "after 'for'")?;
+            // the `;`s in the header do not end the statement, so an error in
+            // it is recovered from here rather than by `synchronize`. Going
+            // back to the `(` keeps track of the parentheses around the error
+            let header_start = parser.checkpoint();
+            let header = match parse_for_header(parser, open) {
+                Ok(header) => Some(header),
+                Err(diagnostic) => {
+                    diagnostics.push(diagnostic);
+                    parser.rewind(header_start);
+                    synchronize_for_header(parser);
+                    None
+                }
+            };
+            let body = parse_statement(parser, diagnostics)?;
+            // the error is already recorded, the body stands in for the loop
+            let Some((init, condition, post)) = header else {
+                return Ok(body);
+            };
+            Ok(Statement::For(
+                Box::new(init),
+                condition,
+                post,
+                Box::new(body),
+                None,
+                start.to(parser.last_span()),
+            ))
+        }
+        Some(Token::Keyword(Keyword::Switch)) => {
+            parser.eat();
+            let control = parse_parenthesized_condition(parser, "switch")?;
+            let body = parse_statement(parser, diagnostics)?;
+            Ok(Statement::Switch(
+                control,
+                Box::new(body),
+                None,
+                start.to(parser.last_span()),
+            ))
+        }
+        Some(Token::Keyword(Keyword::Case)) => {
+            parser.eat();
+            // whether the value is constant is checked once it is labelled
+            let value = parse_expression(parser)?;
+            expect_token(parser, Token::Colon, "after case value")?;
+            let body = parse_statement(parser, diagnostics)?;
+            Ok(Statement::Case(
+                value,
+                Box::new(body),
+                None,
+                start.to(parser.last_span()),
+            ))
+        }
+        Some(Token::Keyword(Keyword::Default)) => {
+            parser.eat();
+            expect_token(parser, Token::Colon, "after 'default'")?;
+            let body = parse_statement(parser, diagnostics)?;
+            Ok(Statement::Default(
+                Box::new(body),
+                None,
+                start.to(parser.last_span()),
+            ))
+        }
+        Some(Token::OpenBrace) => {
+            parser.eat();
+            let blocks = parse_block_items(parser, diagnostics, start)?;
+            Ok(Statement::Compound(blocks, start.to(parser.last_span())))
         }
         Some(_) => {
-            let expr = parse_expression(parser);
-            eat_known_token!(parser, Token::Semicolon);
-            Block::Statement(Statement::Expression(expr))
+            let expr = parse_expression(parser)?;
+            expect_token(parser, Token::Semicolon, "after expression")?;
+            Ok(Statement::Expression(expr, start.to(parser.last_span())))
         }
-        None => panic!("Expected statement but no tokens found"),
+        None => Err(Diagnostic::error(
+            "expected statement before end of input",
+            start,
+        )),
     }
 }
 
-fn parse_declaration(parser: &mut Parser<Token>) -> Block {
+// the name being declared by a variable or function declaration. Keywords are
+// reserved, so `int while = 1;` gets its own error
+fn parse_declared_name(parser: &mut Parser<Token>, kind: &str) -> ParseResult<String> {
+    let span = parser.peek_span();
+    match parser.peek() {
+        Some(Token::Identifier(name)) => {
+            let name = name.clone();
+            parser.eat();
+            Ok(name)
+        }
+        Some(Token::Keyword(keyword)) => Err(Diagnostic::error(
+            format!(
+                "'{}' is a keyword and cannot be used as a {kind} name",
+                keyword.as_str()
+            ),
+            span,
+        )),
+        _ => Err(Diagnostic::error(
+            format!("expected {kind} name before {}", describe_next(parser)),
+            span,
+        )),
+    }
+}
+
+fn parse_declaration(parser: &mut Parser<Token>) -> ParseResult<Block> {
     // Parse a declaration. Declarations must start with the variable's type
     // currently we only support declaring int variables
-    eat_known_token!(parser, Token::Keyword(String::from("int")));
-    let Some(Token::Identifier(var_name)) = parser.eat() else {
-        panic!("Expected variable name identifier")
-    };
+    let start = parser.peek_span();
+    expect_token(
+        parser,
+        Token::Keyword(Keyword::Int),
+        "at start of declaration",
+    )?;
+    let var_name = parse_declared_name(parser, "variable")?;
     let expr = match parser.peek() {
         Some(&Token::EqualSign) => {
-            eat_known_token!(parser, Token::EqualSign);
-            let expr = parse_expression(parser);
-            eat_known_token!(parser, Token::Semicolon);
-            Some(expr)
+            parser.eat();
+            Some(parse_expression(parser)?)
         }
-        Some(&Token::Semicolon) => None,
-        Some(tok) => panic!("Expected = or ; but found {:?}", tok),
-        None => panic!("Expected = or ; but found None"),
+        _ => None,
     };
-    Block::Declaration(var_name, expr)
+    if parser.peek() != Some(&Token::Semicolon) {
+        let expected = match expr {
+            Some(_) => "';'",
+            None => "'=' or ';'",
+        };
+        return Err(Diagnostic::error(
+            format!("expected {expected} after declaration of {var_name}"),
+            parser.peek_span(),
+        ));
+    }
+    parser.eat();
+    Ok(Block::Declaration(
+        var_name,
+        expr,
+        start.to(parser.last_span()),
+    ))
 }
 
-fn parse_block(parser: &mut Parser<Token>) -> Block {
-    match parser.peek() {
-        // currently only variables of type int can be declared
-        Some(Token::Keyword(key)) if key == "int" => parse_declaration(parser),
-        Some(_) => parse_statement(parser),
-        None => panic!("Expected a block but no tokens found"),
+/**
+ * Skips the rest of a broken statement so parsing can resume after it. Stops
+ * after the next `;` or before the `}` closing the enclosing block, skipping
+ * over any nested braces
+ */
+fn synchronize(parser: &mut Parser<Token>) {
+    let mut depth = 0;
+    while let Some(tok) = parser.peek() {
+        match tok {
+            Token::Semicolon if depth == 0 => {
+                parser.eat();
+                return;
+            }
+            Token::CloseBrace if depth == 0 => return,
+            Token::CloseBrace => depth -= 1,
+            Token::OpenBrace => depth += 1,
+            _ => {}
+        }
+        parser.eat();
     }
 }
 
-fn parse_function(parser: &mut Parser<Token>) -> Function {
-    eat_known_token!(parser, Token::Keyword(String::from("int")));
-    let Some(Token::Identifier(name)) = parser.eat() else {
-        panic!("Expected function name identifier")
-    };
-    eat_token_of_kind!(parser, Token::OpenParenthesis);
-    // parse args.. currently only (void) is supported
-    if parser.peek() == Some(&Token::Keyword(String::from("void"))) {
+/**
+ * Skips a broken `for` header from just after its `(`. Stops after the `)`
+ * closing it, skipping over nested parentheses, or before a brace if that
+ * `)` is missing
+ */
+fn synchronize_for_header(parser: &mut Parser<Token>) {
+    let mut depth = 0;
+    while let Some(tok) = parser.peek() {
+        match tok {
+            Token::CloseParenthesis if depth == 0 => {
+                parser.eat();
+                return;
+            }
+            Token::OpenBrace | Token::CloseBrace => return,
+            Token::CloseParenthesis => depth -= 1,
+            Token::OpenParenthesis => depth += 1,
+            _ => {}
+        }
         parser.eat();
     }
-    eat_known_token!(parser, Token::CloseParenthesis);
-    eat_token_of_kind!(parser, Token::OpenBrace);
+}
+
+fn parse_block(
+    parser: &mut Parser<Token>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> ParseResult<Block> {
+    match parser.peek() {
+        // currently only variables of type int can be declared
+        Some(Token::Keyword(Keyword::Int)) => parse_declaration(parser),
+        _ => Ok(Block::Statement(parse_statement(parser, diagnostics)?)),
+    }
+}
+
+// the blocks up to and including the `}` matching the `{` at `open`. Errors
+// in a block are recorded in `diagnostics` and parsing carries on with the
+// next one
+fn parse_block_items(
+    parser: &mut Parser<Token>,
+    diagnostics: &mut Vec<Diagnostic>,
+    open: Span,
+) -> ParseResult<Vec<Block>> {
     let mut blocks: Vec<Block> = vec![];
     while parser.peek() != Some(&Token::CloseBrace) {
-        blocks.push(parse_block(parser));
+        if parser.peek().is_none() {
+            // recovery from an earlier error may have skipped the closing
+            // brace along with some stray opening one, a missing '}' error
+            // would only be noise
+            if !diagnostics.is_empty() {
+                return Ok(blocks);
+            }
+            return Err(
+                Diagnostic::error("expected '}' at end of input", parser.peek_span())
+                    .with_note("to match this '{'", open),
+            );
+        }
+        match parse_block(parser, diagnostics) {
+            Ok(block) => blocks.push(block),
+            Err(diagnostic) => {
+                diagnostics.push(diagnostic);
+                synchronize(parser);
+            }
+        }
     }
-    eat_token_of_kind!(parser, Token::CloseBrace);
+    parser.eat();
+    Ok(blocks)
+}
 
-    Function::Function(name, blocks)
+// errors inside the body are recorded in `diagnostics`, errors in the function
+// header are returned
+fn parse_function(
+    parser: &mut Parser<Token>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> ParseResult<Function> {
+    let start = parser.peek_span();
+    expect_token(
+        parser,
+        Token::Keyword(Keyword::Int),
+        "at start of function definition",
+    )?;
+    let name = parse_declared_name(parser, "function")?;
+    expect_token(parser, Token::OpenParenthesis, "after function name")?;
+    // parse args.. currently only (void) is supported
+    if parser.peek() == Some(&Token::Keyword(Keyword::Void)) {
+        parser.eat();
+    }
+    expect_token(parser, Token::CloseParenthesis, "after parameter list")?;
+    let body_start = parser.peek_span();
+    expect_token(parser, Token::OpenBrace, "before function body")?;
+    let blocks = parse_block_items(parser, diagnostics, body_start)?;
+
+    Ok(Function::Function(
+        name,
+        blocks,
+        start.to(parser.last_span()),
+    ))
 }
 
-pub fn parse_program(parser: &mut Parser<Token>) -> Program {
-    let program = Program::Program(parse_function(parser));
-    if let Some(tok) = parser.peek() {
-        panic!(
-            "Parsed entire program but found extra content starting with token {:?}",
-            tok
-        )
+/**
+ * Parses a whole program, returning every syntax error found in it
+ */
+pub fn parse_program(parser: &mut Parser<Token>) -> Result<Program, Vec<Diagnostic>> {
+    let mut diagnostics: Vec<Diagnostic> = vec![];
+    let function = match parse_function(parser, &mut diagnostics) {
+        Ok(function) => function,
+        Err(diagnostic) => {
+            diagnostics.push(diagnostic);
+            return Err(diagnostics);
+        }
     };
-    program
+    if let Some(tok) = parser.peek() {
+        diagnostics.push(Diagnostic::error(
+            format!("unexpected '{tok}' after function definition"),
+            parser.peek_span(),
+        ));
+    }
+    if diagnostics.is_empty() {
+        Ok(Program::Program(function))
+    } else {
+        Err(diagnostics)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::span::{Span, Spanned};
+
+    fn parser_for(tokens: Vec<Token>) -> Parser<Token> {
+        Parser::new(
+            tokens
+                .into_iter()
+                .enumerate()
+                .map(|(i, token)| Spanned::new(token, Span::new(i, 1, i + 1, 1)))
+                .collect(),
+        )
+    }
+
+    fn parse_source(src: &str) -> Result<Program, Vec<Diagnostic>> {
+        parse_program(&mut Parser::new(lex_contents(src.to_string()).unwrap()))
+    }
+
+    // message and line:column of the only error from parsing `src`
+    fn parse_error(src: &str) -> (String, String) {
+        let diagnostics = parse_source(src).unwrap_err();
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        (
+            diagnostics[0].message.clone(),
+            diagnostics[0].span.to_string(),
+        )
+    }
 
     #[test]
-    #[should_panic = "Expected Keyword(\"int\") but found Keyword(\"return\")"]
-    fn panic_on_keyword_in_bad_position() {
+    fn error_on_keyword_in_bad_position() {
         let program_token_vector = vec![
-            Token::Keyword(String::from("return")),
+            Token::Keyword(Keyword::Return),
             Token::Identifier(String::from("function_name")),
             Token::OpenParenthesis,
             Token::CloseParenthesis,
             Token::OpenBrace,
-            Token::Keyword(String::from("return")),
-            Token::Constant(String::from("2")),
+            Token::Keyword(Keyword::Return),
+            Token::Constant(2, IntegerType::Int),
             Token::Semicolon,
             Token::CloseBrace,
         ];
-        parse_program(&mut Parser::new(program_token_vector));
+        let diagnostics = parse_program(&mut parser_for(program_token_vector)).unwrap_err();
+        assert_eq!(
+            diagnostics[0],
+            Diagnostic::error(
+                "expected 'int' at start of function definition",
+                Span::new(0, 1, 1, 1)
+            )
+        );
     }
 
     #[test]
-    #[should_panic = "Invalid expression. Cannot begin with OpenBrace"]
-    fn panic_on_malformed_expression() {
+    fn keyword_as_declared_name() {
+        assert_eq!(
+            parse_error("int main(void) { int while = 1; }"),
+            (
+                String::from("'while' is a keyword and cannot be used as a variable name"),
+                String::from("1:22")
+            )
+        );
+        assert_eq!(
+            parse_error("int _Bool(void) { return 0; }"),
+            (
+                String::from("'_Bool' is a keyword and cannot be used as a function name"),
+                String::from("1:5")
+            )
+        );
+    }
+
+    #[test]
+    fn error_on_malformed_expression() {
         let program_token_vector = vec![
-            Token::Keyword(String::from("int")),
+            Token::Keyword(Keyword::Int),
             Token::Identifier(String::from("function_name")),
             Token::OpenParenthesis,
             Token::CloseParenthesis,
             Token::OpenBrace,
-            Token::Keyword(String::from("return")),
+            Token::Keyword(Keyword::Return),
             Token::OpenBrace,
-            Token::Constant(String::from("2")),
+            Token::Constant(2, IntegerType::Int),
             Token::CloseBrace,
             Token::Identifier(String::from("variable_name")),
             Token::CloseBrace,
         ];
-        parse_program(&mut Parser::new(program_token_vector));
+        let diagnostics = parse_program(&mut parser_for(program_token_vector)).unwrap_err();
+        assert_eq!(diagnostics[0].message, "expected expression before '{'");
+        assert_eq!(diagnostics[0].span, Span::new(6, 1, 7, 1));
+    }
+
+    #[test]
+    fn missing_tokens_are_described_by_context() {
+        assert_eq!(
+            parse_error("int main(void) {\n    return 2\n}"),
+            (
+                String::from("expected ';' after return statement"),
+                String::from("3:1")
+            )
+        );
+        assert_eq!(
+            parse_error("int main(void) { int a 1; }"),
+            (
+                String::from("expected '=' or ';' after declaration of a"),
+                String::from("1:24")
+            )
+        );
+        assert_eq!(
+            parse_error("int main(void) { return 1; } int"),
+            (
+                String::from("unexpected 'int' after function definition"),
+                String::from("1:30")
+            )
+        );
+        assert_eq!(
+            parse_error(""),
+            (
+                String::from("expected 'int' at start of function definition"),
+                String::from("1:1")
+            )
+        );
+    }
+
+    #[test]
+    fn unclosed_delimiters_point_at_opening() {
+        let diagnostic = &parse_source("int main(void) { return (1 + 2; }").unwrap_err()[0];
+        assert_eq!(diagnostic.message, "expected ')' before ';'");
+        assert_eq!(diagnostic.notes[0].message, "to match this '('");
+        assert_eq!(diagnostic.notes[0].span, Span::new(24, 1, 25, 1));
+
+        let diagnostic = &parse_source("int main(void) {\n    return 0;\n").unwrap_err()[0];
+        assert_eq!(diagnostic.message, "expected '}' at end of input");
+        assert_eq!(diagnostic.notes[0].span, Span::new(15, 1, 16, 1));
+    }
+
+    #[test]
+    fn recover_after_errors_in_statements() {
+        let diagnostics =
+            parse_source("int main(void) {\n  int a = 1 +;\n  a = (2 * 3;\n  return a\n}")
+                .unwrap_err();
+        let errors: Vec<(String, String)> = diagnostics
+            .into_iter()
+            .map(|diagnostic| (diagnostic.message, diagnostic.span.to_string()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    String::from("expected expression before ';'"),
+                    String::from("2:14")
+                ),
+                (
+                    String::from("expected ')' before ';'"),
+                    String::from("3:13")
+                ),
+                (
+                    String::from("expected ';' after return statement"),
+                    String::from("5:1")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn recover_inside_nested_blocks() {
+        let diagnostics =
+            parse_source("int main(void) {\n  { a = ; { b = ; } }\n  return 0 }").unwrap_err();
+        let errors: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.message.as_str(), diagnostic.span.to_string()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("expected expression before ';'", String::from("2:9")),
+                ("expected expression before ';'", String::from("2:17")),
+                ("expected ';' after return statement", String::from("3:12")),
+            ]
+        );
+    }
+
+    #[test]
+    fn recover_at_end_of_for_header() {
+        // neither the `;`s in the header nor the nested `)` end the recovery
+        assert_eq!(
+            parse_error("int main(void) {\n  for (a; (b; c); ) a = 1;\n  return a;\n}"),
+            (
+                String::from("expected ')' before ';'"),
+                String::from("2:13")
+            )
+        );
+        assert_eq!(
+            parse_error("int main(void) {\n  for (int i = 0, j; ;) { i = 1; }\n  return 0;\n}"),
+            (
+                String::from("expected ';' after declaration of i"),
+                String::from("2:17")
+            )
+        );
+    }
+
+    #[test]
+    fn expression_spans_cover_operands() {
+        let Program::Program(Function::Function(_, blocks, function_span)) =
+            parse_source("int main(void) {\n  return 1 + 22;\n}").unwrap();
+        assert_eq!(function_span, Span::new(0, 1, 1, 35));
+        let Block::Statement(Statement::Return(expr, return_span)) = &blocks[0] else {
+            panic!("Expected a return statement");
+        };
+        assert_eq!(*return_span, Span::new(19, 2, 3, 14));
+        assert_eq!(expr.span(), Span::new(26, 2, 10, 6));
+    }
+
+    #[test]
+    fn assignment_is_right_associative() {
+        let Program::Program(Function::Function(_, blocks, _)) =
+            parse_source("int main(void) { a = b = 1 - 2 - 3; }").unwrap();
+        let Block::Statement(Statement::Expression(Expression::Assignment(left, right, _), _)) =
+            &blocks[0]
+        else {
+            panic!("Expected an assignment");
+        };
+        assert!(matches!(left.as_ref(), Expression::Var(name, _) if name == "a"));
+        let Expression::Assignment(_, value, _) = right.as_ref() else {
+            panic!("Expected `b = 1 - 2 - 3` on the right");
+        };
+        let Expression::Binary(BinaryOperator::Subtract, left, _, _) = value.as_ref() else {
+            panic!("Expected a subtraction");
+        };
+        assert!(matches!(
+            left.as_ref(),
+            Expression::Binary(BinaryOperator::Subtract, _, _, _)
+        ));
+    }
+
+    #[test]
+    fn else_belongs_to_innermost_if() {
+        let Program::Program(Function::Function(_, blocks, _)) =
+            parse_source("int main(void) { if (a) if (b) return 1; else return 2; }").unwrap();
+        let Block::Statement(Statement::If(_, then, None, _)) = &blocks[0] else {
+            panic!("Expected an if statement without else");
+        };
+        assert!(matches!(then.as_ref(), Statement::If(_, _, Some(_), _)));
+    }
+
+    #[test]
+    fn parse_for_headers() {
+        let Program::Program(Function::Function(_, blocks, _)) =
+            parse_source("int main(void) { for (int i = 0; ; ) ; for (; i; i = 1) ; }").unwrap();
+        let Block::Statement(Statement::For(init, None, None, _, None, _)) = &blocks[0] else {
+            panic!("Expected a for loop without condition or post expression");
+        };
+        assert!(matches!(init.as_ref(), ForInit::Declaration(name, Some(_), _) if name == "i"));
+        let Block::Statement(Statement::For(init, Some(_), Some(_), _, None, _)) = &blocks[1]
+        else {
+            panic!("Expected a for loop with condition and post expression");
+        };
+        assert_eq!(init.as_ref(), &ForInit::Expression(None));
+    }
+
+    #[test]
+    fn error_on_do_without_while() {
+        assert_eq!(
+            parse_error("int main(void) { do ; return 1; }"),
+            (
+                String::from("expected 'while' after body of 'do'"),
+                String::from("1:23")
+            )
+        );
+    }
+
+    #[test]
+    fn parse_switch_labels() {
+        let Program::Program(Function::Function(_, blocks, _)) =
+            parse_source("int main(void) { switch (a) { case 1: case 2: b; default: ; } }")
+                .unwrap();
+        let Block::Statement(Statement::Switch(_, body, None, _)) = &blocks[0] else {
+            panic!("Expected an unlabelled switch");
+        };
+        let Statement::Compound(items, _) = body.as_ref() else {
+            panic!("Expected a compound switch body");
+        };
+        let Block::Statement(Statement::Case(Expression::Constant(1, _), inner, None, _)) =
+            &items[0]
+        else {
+            panic!("Expected `case 1:`");
+        };
+        assert!(matches!(
+            inner.as_ref(),
+            Statement::Case(Expression::Constant(2, _), _, None, _)
+        ));
+        assert!(matches!(
+            &items[1],
+            Block::Statement(Statement::Default(_, None, _))
+        ));
+    }
+
+    #[test]
+    fn error_on_case_without_colon() {
+        assert_eq!(
+            parse_error("int main(void) { switch (a) { case 1 ; } }"),
+            (
+                String::from("expected ':' after case value"),
+                String::from("1:38")
+            )
+        );
+    }
+
+    #[test]
+    fn conditional_precedence_and_associativity() {
+        let Program::Program(Function::Function(_, blocks, _)) =
+            parse_source("int main(void) { a = b || c ? 1 : d ? 2 : 3; }").unwrap();
+        let Block::Statement(Statement::Expression(Expression::Assignment(_, value, _), _)) =
+            &blocks[0]
+        else {
+            panic!("Expected an assignment");
+        };
+        let Expression::Conditional(condition, _, otherwise, _) = value.as_ref() else {
+            panic!("Expected a conditional on the right of the assignment");
+        };
+        assert!(matches!(
+            condition.as_ref(),
+            Expression::Binary(BinaryOperator::LogicalOr, _, _, _)
+        ));
+        assert!(matches!(
+            otherwise.as_ref(),
+            Expression::Conditional(_, _, _, _)
+        ));
+    }
+
+    #[test]
+    fn error_on_conditional_without_colon() {
+        let diagnostics = parse_source("int main(void) { return a ? 1; }").unwrap_err();
+        assert_eq!(diagnostics[0].message, "expected ':' before ';'");
+        assert_eq!(diagnostics[0].span.to_string(), "1:30");
+        assert_eq!(diagnostics[0].notes[0].message, "to match this '?'");
+        assert_eq!(diagnostics[0].notes[0].span.to_string(), "1:27");
+    }
+
+    #[test]
+    fn error_on_constant_wider_than_int() {
+        assert_eq!(
+            parse_error("int main(void) { return 2147483648; }"),
+            (
+                String::from("2147483648 has type long but only int constants are supported"),
+                String::from("1:25")
+            )
+        );
+        assert_eq!(
+            parse_error("int main(void) { return 10u; }"),
+            (
+                String::from("10 has type unsigned int but only int constants are supported"),
+                String::from("1:25")
+            )
+        );
+    }
+
+    #[test]
+    fn concatenate_adjacent_string_literals() {
+        let Program::Program(Function::Function(_, blocks, _)) =
+            parse_source("int main(void) { \"a\\t\" \"b\" \"\"; 'c'; }").unwrap();
+        assert_eq!(
+            blocks,
+            vec![
+                Block::Statement(Statement::Expression(
+                    Expression::StringLiteral(b"a\tb".to_vec(), Span::new(17, 1, 18, 12)),
+                    Span::new(17, 1, 18, 13)
+                )),
+                Block::Statement(Statement::Expression(
+                    Expression::CharLiteral(b'c', Span::new(31, 1, 32, 3)),
+                    Span::new(31, 1, 32, 4)
+                )),
+            ]
+        );
     }
 }
@@ -0,0 +1,460 @@
+use super::ast::*;
+use super::visit::{VisitorMut, walk_statement_mut};
+use crate::core::diagnostic::Diagnostic;
+use crate::core::span::Span;
+
+/**
+ * A statement `break`, `continue` or a case label can refer to. A switch
+ * collects its cases, with the span of each for duplicate errors, until its
+ * body has been labelled
+ */
+enum Enclosing {
+    Loop(String),
+    Switch(SwitchLabels, Vec<Span>, Option<Span>),
+}
+
+/**
+ * Gives every loop and switch a label unique to the program, every `break`
+ * and `continue` the label of the statement it leaves, and every case and
+ * default a label of its own in the innermost switch
+ */
+struct LoopLabeler {
+    enclosing: Vec<Enclosing>,
+    label_count: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/**
+ * The value of an integer constant expression, like `-1` or `'a' + 1`. `None`
+ * for anything that needs the program to run, or has no defined value
+ */
+fn constant_value(expression: &Expression) -> Option<i32> {
+    match expression {
+        Expression::Constant(value, _) => Some(*value),
+        // char is signed on x86-64, as in to_tacky
+        Expression::CharLiteral(value, _) => Some(*value as i8 as i32),
+        Expression::Unary(operator, operand, _) => {
+            let operand = constant_value(operand)?;
+            match operator {
+                UnaryOperator::Negation => operand.checked_neg(),
+                UnaryOperator::Complement => Some(!operand),
+                UnaryOperator::Not => Some((operand == 0) as i32),
+            }
+        }
+        Expression::Binary(operator, left, right, _) => {
+            let left = constant_value(left)?;
+            let right = constant_value(right)?;
+            match operator {
+                BinaryOperator::Add => left.checked_add(right),
+                BinaryOperator::Subtract => left.checked_sub(right),
+                BinaryOperator::Multiply => left.checked_mul(right),
+                BinaryOperator::Divide => left.checked_div(right),
+                BinaryOperator::Modulo => left.checked_rem(right),
+                BinaryOperator::LeftShift => u32::try_from(right)
+                    .ok()
+                    .and_then(|right| left.checked_shl(right)),
+                BinaryOperator::RightShift => u32::try_from(right)
+                    .ok()
+                    .and_then(|right| left.checked_shr(right)),
+                BinaryOperator::BitwiseAnd => Some(left & right),
+                BinaryOperator::BitwiseXor => Some(left ^ right),
+                BinaryOperator::BitwiseOr => Some(left | right),
+                BinaryOperator::LogicalAnd => Some((left != 0 && right != 0) as i32),
+                BinaryOperator::LogicalOr => Some((left != 0 || right != 0) as i32),
+                BinaryOperator::IsEqual => Some((left == right) as i32),
+                BinaryOperator::NotEqual => Some((left != right) as i32),
+                BinaryOperator::LessThan => Some((left < right) as i32),
+                BinaryOperator::LessThanOrEqual => Some((left <= right) as i32),
+                BinaryOperator::GreaterThan => Some((left > right) as i32),
+                BinaryOperator::GreaterThanOrEqual => Some((left >= right) as i32),
+                BinaryOperator::Equal => None,
+            }
+        }
+        Expression::Conditional(condition, then, otherwise, _) => {
+            match constant_value(condition)? {
+                0 => constant_value(otherwise),
+                _ => constant_value(then),
+            }
+        }
+        Expression::StringLiteral(..) | Expression::Var(..) | Expression::Assignment(..) => None,
+    }
+}
+
+impl LoopLabeler {
+    fn new_label(&mut self, kind: &str) -> String {
+        let label = format!("{kind}.{}", self.label_count);
+        self.label_count += 1;
+        label
+    }
+
+    fn error(&mut self, message: String, span: Span) {
+        self.diagnostics.push(Diagnostic::error(message, span));
+    }
+
+    // the label `break` jumps past: that of the innermost loop or switch
+    fn break_target(&mut self, span: Span) -> Option<String> {
+        let target = self.enclosing.last().map(|enclosing| match enclosing {
+            Enclosing::Loop(label) => label.clone(),
+            Enclosing::Switch(labels, _, _) => labels.label.clone(),
+        });
+        if target.is_none() {
+            self.error(
+                String::from("'break' statement not in a loop or switch"),
+                span,
+            );
+        }
+        target
+    }
+
+    // `continue` goes to the next iteration of the innermost loop, even from
+    // inside a switch
+    fn continue_target(&mut self, span: Span) -> Option<String> {
+        let target = self
+            .enclosing
+            .iter()
+            .rev()
+            .find_map(|enclosing| match enclosing {
+                Enclosing::Loop(label) => Some(label.clone()),
+                Enclosing::Switch(..) => None,
+            });
+        if target.is_none() {
+            self.error(String::from("'continue' statement not in a loop"), span);
+        }
+        target
+    }
+
+    // the switch a case or default belongs to, even from inside a loop
+    fn innermost_switch(&mut self) -> Option<&mut Enclosing> {
+        self.enclosing
+            .iter_mut()
+            .rev()
+            .find(|enclosing| matches!(enclosing, Enclosing::Switch(..)))
+    }
+
+    fn label_case(&mut self, value: &Expression, span: Span) -> Option<String> {
+        if self.innermost_switch().is_none() {
+            self.error(
+                String::from("'case' label not within a switch statement"),
+                span,
+            );
+            return None;
+        }
+        let Some(value) = constant_value(value) else {
+            self.error(
+                String::from("case label does not reduce to an integer constant"),
+                value.span(),
+            );
+            return None;
+        };
+        let Some(Enclosing::Switch(labels, case_spans, _)) = self.innermost_switch() else {
+            unreachable!("the enclosing switch was checked above");
+        };
+        if let Some(index) = labels.cases.iter().position(|(case, _)| *case == value) {
+            let previous = case_spans[index];
+            self.diagnostics.push(
+                Diagnostic::error(format!("duplicate case value {value}"), span)
+                    .with_note("previously used here", previous),
+            );
+            return None;
+        }
+        let label = format!("{}_CASE{}", labels.label, labels.cases.len());
+        labels.cases.push((value, label.clone()));
+        case_spans.push(span);
+        Some(label)
+    }
+
+    fn label_default(&mut self, span: Span) -> Option<String> {
+        let Some(Enclosing::Switch(labels, _, default_span)) = self.innermost_switch() else {
+            self.error(
+                String::from("'default' label not within a switch statement"),
+                span,
+            );
+            return None;
+        };
+        if let Some(previous) = *default_span {
+            self.diagnostics.push(
+                Diagnostic::error("multiple default labels in one switch", span)
+                    .with_note("previous default label is here", previous),
+            );
+            return None;
+        }
+        let label = format!("{}_DEFAULT", labels.label);
+        labels.default = Some(label.clone());
+        *default_span = Some(span);
+        Some(label)
+    }
+}
+
+impl VisitorMut for LoopLabeler {
+    fn visit_statement_mut(&mut self, statement: &mut Statement) {
+        match statement {
+            Statement::Break(label, span) => *label = self.break_target(*span),
+            Statement::Continue(label, span) => *label = self.continue_target(*span),
+            Statement::While(_, _, label, _)
+            | Statement::DoWhile(_, _, label, _)
+            | Statement::For(_, _, _, _, label, _) => {
+                let new_label = self.new_label("loop");
+                *label = Some(new_label.clone());
+                self.enclosing.push(Enclosing::Loop(new_label));
+                walk_statement_mut(self, statement);
+                self.enclosing.pop();
+            }
+            Statement::Switch(..) => {
+                let labels = SwitchLabels {
+                    label: self.new_label("switch"),
+                    cases: vec![],
+                    default: None,
+                };
+                self.enclosing.push(Enclosing::Switch(labels, vec![], None));
+                walk_statement_mut(self, statement);
+                if let Some(Enclosing::Switch(labels, _, _)) = self.enclosing.pop()
+                    && let Statement::Switch(_, _, switch_labels, _) = statement
+                {
+                    *switch_labels = Some(labels);
+                }
+            }
+            Statement::Case(value, _, label, span) => {
+                *label = self.label_case(value, *span);
+                walk_statement_mut(self, statement);
+            }
+            Statement::Default(_, label, span) => {
+                *label = self.label_default(*span);
+                walk_statement_mut(self, statement);
+            }
+            _ => walk_statement_mut(self, statement),
+        }
+    }
+}
+
+/**
+ * Labels loops and switches so `to_tacky` knows where each `break`,
+ * `continue` and case jumps to. Reports any of them outside of the statement
+ * they belong in, and case values that are not constant or used twice
+ */
+pub fn label_loops(mut program: Program) -> Result<Program, Vec<Diagnostic>> {
+    let mut labeler = LoopLabeler {
+        enclosing: vec![],
+        label_count: 0,
+        diagnostics: vec![],
+    };
+    labeler.visit_program_mut(&mut program);
+    match labeler.diagnostics.is_empty() {
+        true => Ok(program),
+        false => Err(labeler.diagnostics),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::c::from_lexical::parse_program;
+    use crate::c::lexer::lex_contents;
+    use crate::c::visit::{Visitor, walk_statement};
+    use crate::core::parser::Parser;
+
+    fn label(src: &str) -> Result<Program, Vec<Diagnostic>> {
+        label_loops(
+            parse_program(&mut Parser::new(lex_contents(src.to_string()).unwrap())).unwrap(),
+        )
+    }
+
+    // message and line:column of every error from labelling `src`
+    fn label_errors(src: &str) -> Vec<(String, String)> {
+        label(src)
+            .unwrap_err()
+            .into_iter()
+            .map(|diagnostic| (diagnostic.message, diagnostic.span.to_string()))
+            .collect()
+    }
+
+    // the label of every loop, switch, break, continue and case, in order
+    #[derive(Default)]
+    struct Labels(Vec<String>);
+
+    impl Visitor for Labels {
+        fn visit_statement(&mut self, statement: &Statement) {
+            match statement {
+                Statement::Break(label, _)
+                | Statement::Continue(label, _)
+                | Statement::While(_, _, label, _)
+                | Statement::DoWhile(_, _, label, _)
+                | Statement::For(_, _, _, _, label, _) => {
+                    self.0.push(label.clone().expect("every loop is labelled"))
+                }
+                Statement::Switch(_, _, labels, _) => self.0.push(
+                    labels
+                        .as_ref()
+                        .expect("every switch is labelled")
+                        .label
+                        .clone(),
+                ),
+                Statement::Case(_, _, label, _) | Statement::Default(_, label, _) => {
+                    self.0.push(label.clone().expect("every case is labelled"))
+                }
+                _ => {}
+            }
+            walk_statement(self, statement);
+        }
+    }
+
+    #[test]
+    fn break_and_continue_take_innermost_loop() {
+        let program = label(
+            "int main(void) { while (1) { for (;;) { if (a) break; continue; } do break; while (1); continue; } }",
+        )
+        .unwrap();
+        let mut labels = Labels::default();
+        labels.visit_program(&program);
+        assert_eq!(
+            labels.0,
+            vec![
+                "loop.0", "loop.1", "loop.1", "loop.1", "loop.2", "loop.2", "loop.0"
+            ]
+        );
+    }
+
+    #[test]
+    fn error_outside_of_loop() {
+        let diagnostics =
+            label("int main(void) { break; while (1) ; if (1) continue; }").unwrap_err();
+        let errors: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.message.as_str(), diagnostic.span.to_string()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    "'break' statement not in a loop or switch",
+                    String::from("1:18")
+                ),
+                ("'continue' statement not in a loop", String::from("1:44")),
+            ]
+        );
+    }
+
+    #[test]
+    fn label_switch_cases() {
+        let program = label(
+            "int main(void) { switch (a) { case 1 + 1: break; default: case -1: switch (b) case 'a': ; case ~0 ? 3 : 4: ; } }",
+        )
+        .unwrap();
+        let Program::Program(Function::Function(_, blocks, _)) = &program;
+        let Block::Statement(Statement::Switch(_, _, Some(labels), _)) = &blocks[0] else {
+            panic!("expected a labelled switch, got {:?}", blocks[0]);
+        };
+        assert_eq!(
+            *labels,
+            SwitchLabels {
+                label: String::from("switch.0"),
+                cases: vec![
+                    (2, String::from("switch.0_CASE0")),
+                    (-1, String::from("switch.0_CASE1")),
+                    (3, String::from("switch.0_CASE2")),
+                ],
+                default: Some(String::from("switch.0_DEFAULT")),
+            }
+        );
+        let mut found = Labels::default();
+        found.visit_program(&program);
+        assert_eq!(
+            found.0,
+            vec![
+                "switch.0",
+                "switch.0_CASE0",
+                "switch.0",
+                "switch.0_DEFAULT",
+                "switch.0_CASE1",
+                "switch.1",
+                "switch.1_CASE0",
+                "switch.0_CASE2",
+            ]
+        );
+    }
+
+    #[test]
+    fn break_leaves_switch_and_continue_leaves_loop() {
+        let program = label(
+            "int main(void) { while (1) switch (a) { case 0: while (1) { case 1: break; } continue; default: break; } }",
+        )
+        .unwrap();
+        let mut labels = Labels::default();
+        labels.visit_program(&program);
+        assert_eq!(
+            labels.0,
+            vec![
+                "loop.0",
+                "switch.1",
+                "switch.1_CASE0",
+                "loop.2",
+                "switch.1_CASE1",
+                "loop.2",
+                "loop.0",
+                "switch.1_DEFAULT",
+                "switch.1",
+            ]
+        );
+    }
+
+    #[test]
+    fn error_on_case_outside_of_switch() {
+        assert_eq!(
+            label_errors("int main(void) { case 1: ; while (1) default: ; case a: ; }"),
+            vec![
+                (
+                    String::from("'case' label not within a switch statement"),
+                    String::from("1:18")
+                ),
+                (
+                    String::from("'default' label not within a switch statement"),
+                    String::from("1:38")
+                ),
+                // not being in a switch is the error, not the non-constant value
+                (
+                    String::from("'case' label not within a switch statement"),
+                    String::from("1:49")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn error_on_non_constant_case() {
+        assert_eq!(
+            label_errors("int main(void) { switch (a) { case a: ; case 1 / 0: ; case 1: ; } }"),
+            vec![
+                (
+                    String::from("case label does not reduce to an integer constant"),
+                    String::from("1:36")
+                ),
+                (
+                    String::from("case label does not reduce to an integer constant"),
+                    String::from("1:46")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn error_on_duplicate_case() {
+        let diagnostics =
+            label("int main(void) { switch (a) {\n  case 3: ;\n  case 1 + 2: ; } }").unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "duplicate case value 3");
+        assert_eq!(diagnostics[0].span.to_string(), "3:3");
+        assert_eq!(diagnostics[0].notes[0].span.to_string(), "2:3");
+    }
+
+    #[test]
+    fn error_on_multiple_defaults() {
+        let diagnostics =
+            label("int main(void) { switch (a) {\n  default: ;\n  default: ; } }").unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "multiple default labels in one switch"
+        );
+        assert_eq!(diagnostics[0].span.to_string(), "3:3");
+        assert_eq!(diagnostics[0].notes[0].span.to_string(), "2:3");
+    }
+}
@@ -1,15 +1,176 @@
-use regex::Regex;
+use crate::core::lexer::{LexError, Lexer};
+use crate::core::source_map::SourceMap;
+use crate::core::span::Spanned;
+use std::fmt;
+
+/**
+ * Type of an integer constant, derived from its suffix and whether its value
+ * fits in the smaller candidate types (C17 6.4.4.1). Sizes follow the LP64
+ * model used on x86-64 linux
+ */
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum IntegerType {
+    Int,
+    UnsignedInt,
+    Long,
+    UnsignedLong,
+    LongLong,
+    UnsignedLongLong,
+}
+
+impl IntegerType {
+    pub fn max_value(&self) -> u64 {
+        match self {
+            IntegerType::Int => i32::MAX as u64,
+            IntegerType::UnsignedInt => u32::MAX as u64,
+            IntegerType::Long | IntegerType::LongLong => i64::MAX as u64,
+            IntegerType::UnsignedLong | IntegerType::UnsignedLongLong => u64::MAX,
+        }
+    }
+}
+
+impl fmt::Display for IntegerType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let spelling = match self {
+            IntegerType::Int => "int",
+            IntegerType::UnsignedInt => "unsigned int",
+            IntegerType::Long => "long",
+            IntegerType::UnsignedLong => "unsigned long",
+            IntegerType::LongLong => "long long",
+            IntegerType::UnsignedLongLong => "unsigned long long",
+        };
+        write!(f, "{spelling}")
+    }
+}
+
+/**
+ * Every keyword of C17 (6.4.1). Keywords are reserved and can never be used
+ * as identifiers
+ */
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Keyword {
+    Auto,
+    Break,
+    Case,
+    Char,
+    Const,
+    Continue,
+    Default,
+    Do,
+    Double,
+    Else,
+    Enum,
+    Extern,
+    Float,
+    For,
+    Goto,
+    If,
+    Inline,
+    Int,
+    Long,
+    Register,
+    Restrict,
+    Return,
+    Short,
+    Signed,
+    Sizeof,
+    Static,
+    Struct,
+    Switch,
+    Typedef,
+    Union,
+    Unsigned,
+    Void,
+    Volatile,
+    While,
+    Alignas,
+    Alignof,
+    Atomic,
+    Bool,
+    Complex,
+    Generic,
+    Imaginary,
+    Noreturn,
+    StaticAssert,
+    ThreadLocal,
+}
+
+const KEYWORDS: [(&str, Keyword); 44] = [
+    ("auto", Keyword::Auto),
+    ("break", Keyword::Break),
+    ("case", Keyword::Case),
+    ("char", Keyword::Char),
+    ("const", Keyword::Const),
+    ("continue", Keyword::Continue),
+    ("default", Keyword::Default),
+    ("do", Keyword::Do),
+    ("double", Keyword::Double),
+    ("else", Keyword::Else),
+    ("enum", Keyword::Enum),
+    ("extern", Keyword::Extern),
+    ("float", Keyword::Float),
+    ("for", Keyword::For),
+    ("goto", Keyword::Goto),
+    ("if", Keyword::If),
+    ("inline", Keyword::Inline),
+    ("int", Keyword::Int),
+    ("long", Keyword::Long),
+    ("register", Keyword::Register),
+    ("restrict", Keyword::Restrict),
+    ("return", Keyword::Return),
+    ("short", Keyword::Short),
+    ("signed", Keyword::Signed),
+    ("sizeof", Keyword::Sizeof),
+    ("static", Keyword::Static),
+    ("struct", Keyword::Struct),
+    ("switch", Keyword::Switch),
+    ("typedef", Keyword::Typedef),
+    ("union", Keyword::Union),
+    ("unsigned", Keyword::Unsigned),
+    ("void", Keyword::Void),
+    ("volatile", Keyword::Volatile),
+    ("while", Keyword::While),
+    ("_Alignas", Keyword::Alignas),
+    ("_Alignof", Keyword::Alignof),
+    ("_Atomic", Keyword::Atomic),
+    ("_Bool", Keyword::Bool),
+    ("_Complex", Keyword::Complex),
+    ("_Generic", Keyword::Generic),
+    ("_Imaginary", Keyword::Imaginary),
+    ("_Noreturn", Keyword::Noreturn),
+    ("_Static_assert", Keyword::StaticAssert),
+    ("_Thread_local", Keyword::ThreadLocal),
+];
+
+impl Keyword {
+    /**
+     * The keyword as written in source
+     */
+    pub fn as_str(&self) -> &'static str {
+        KEYWORDS
+            .iter()
+            .find(|(_, keyword)| keyword == self)
+            .map(|(spelling, _)| *spelling)
+            .expect("every keyword is in KEYWORDS")
+    }
+}
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     Identifier(String),
-    Constant(String),
-    Keyword(String),
+    Constant(u64, IntegerType),
+    CharLiteral(u8),        // 'a', value after escape decoding
+    StringLiteral(Vec<u8>), // "a\tb", bytes after escape decoding, no NUL
+    Keyword(Keyword),
     OpenParenthesis,
     CloseParenthesis,
-    OpenBrace,
-    CloseBrace,
+    OpenBrace,          // { or <%
+    CloseBrace,         // } or %>
+    OpenSquareBracket,  // [ or <:
+    CloseSquareBracket, // ] or :>
     Semicolon,
+    Period,
+    Arrow, // ->
     Tilde,
     Hyphen,
     Plus,
@@ -23,118 +184,642 @@ pub enum Token {
     CloseAngleBracket,
     DoubleOpenAngleBracket,  // <<
     DoubleCloseAngleBracket, // >>
-    _Decrement,              // -- currently unsupported, will panic
+    Increment,               // ++
+    Decrement,               // --
     ExclamationPoint,
     EqualSign,
-    DoubleAmpersand,  // &&
-    DoublePipe,       // ||
-    DoubleEqual,      // ==
-    NotEqual,         // !=
-    LessThanEqual,    // <=
-    GreaterThanEqual, // >=
+    PlusEqual,                    // +=
+    HyphenEqual,                  // -=
+    StarEqual,                    // *=
+    SlashEqual,                   // /=
+    ModuloEqual,                  // %=
+    AmpersandEqual,               // &=
+    PipeEqual,                    // |=
+    CaretEqual,                   // ^=
+    DoubleOpenAngleBracketEqual,  // <<=
+    DoubleCloseAngleBracketEqual, // >>=
+    DoubleAmpersand,              // &&
+    DoublePipe,                   // ||
+    DoubleEqual,                  // ==
+    NotEqual,                     // !=
+    LessThanEqual,                // <=
+    GreaterThanEqual,             // >=
+    Comma,
+    QuestionMark,
+    Colon,
+    Ellipsis,           // ...
+    Hash,               // # or %:, only meaningful to the preprocessor
+    DoubleHash,         // ## or %:%:, only meaningful to the preprocessor
+    Newline,            // end of a logical line, only produced for the preprocessor
+    HeaderName(String), // <stdio.h> right after #include, only produced for the preprocessor
+    PpNumber(String),   // 1.5 or 0x1F as written, only produced for the preprocessor
+    // text that is not a token and why, only produced for the preprocessor,
+    // which reports it unless it is in a skipped group
+    Invalid(String, String),
 }
 
-fn classify_token(token_content: &str) -> Token {
-    /*
-     * Given regex patterns for each token type.
-     * Any other value you encounter at this point should be handled by exact match
-     */
+/**
+ * Writes a token the way it would be spelled in source, for diagnostics
+ */
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let punctuator = match self {
+            Token::Identifier(name) => return write!(f, "{name}"),
+            Token::Constant(value, _) => return write!(f, "{value}"),
+            Token::CharLiteral(value) => {
+                return write!(f, "'{}'", (*value as char).escape_default());
+            }
+            Token::StringLiteral(bytes) => {
+                return write!(f, "\"{}\"", bytes.escape_ascii());
+            }
+            Token::Keyword(keyword) => keyword.as_str(),
+            Token::HeaderName(name) => return write!(f, "<{name}>"),
+            Token::PpNumber(spelling) | Token::Invalid(spelling, _) => {
+                return write!(f, "{spelling}");
+            }
+            Token::Newline => "newline",
+            Token::OpenParenthesis => "(",
+            Token::CloseParenthesis => ")",
+            Token::OpenBrace => "{",
+            Token::CloseBrace => "}",
+            Token::OpenSquareBracket => "[",
+            Token::CloseSquareBracket => "]",
+            Token::Semicolon => ";",
+            Token::Period => ".",
+            Token::Arrow => "->",
+            Token::Tilde => "~",
+            Token::Hyphen => "-",
+            Token::Plus => "+",
+            Token::Star => "*",
+            Token::Slash => "/",
+            Token::Modulo => "%",
+            Token::Ampersand => "&",
+            Token::Pipe => "|",
+            Token::Caret => "^",
+            Token::OpenAngleBracket => "<",
+            Token::CloseAngleBracket => ">",
+            Token::DoubleOpenAngleBracket => "<<",
+            Token::DoubleCloseAngleBracket => ">>",
+            Token::Increment => "++",
+            Token::Decrement => "--",
+            Token::ExclamationPoint => "!",
+            Token::EqualSign => "=",
+            Token::PlusEqual => "+=",
+            Token::HyphenEqual => "-=",
+            Token::StarEqual => "*=",
+            Token::SlashEqual => "/=",
+            Token::ModuloEqual => "%=",
+            Token::AmpersandEqual => "&=",
+            Token::PipeEqual => "|=",
+            Token::CaretEqual => "^=",
+            Token::DoubleOpenAngleBracketEqual => "<<=",
+            Token::DoubleCloseAngleBracketEqual => ">>=",
+            Token::DoubleAmpersand => "&&",
+            Token::DoublePipe => "||",
+            Token::DoubleEqual => "==",
+            Token::NotEqual => "!=",
+            Token::LessThanEqual => "<=",
+            Token::GreaterThanEqual => ">=",
+            Token::Comma => ",",
+            Token::QuestionMark => "?",
+            Token::Colon => ":",
+            Token::Ellipsis => "...",
+            Token::Hash => "#",
+            Token::DoubleHash => "##",
+        };
+        write!(f, "{punctuator}")
+    }
+}
 
-    let content_copy: String = token_content.to_string();
-    let identifier_rgx = Regex::new(r"^[a-zA-Z_]\w*\b$").unwrap();
-    let constant_rgx = Regex::new(r"^[0-9]+\b$").unwrap();
-    let keyword_rgx = Regex::new(r"^(int|return|void)$").unwrap();
-
-    if keyword_rgx.is_match(token_content) {
-        return Token::Keyword(content_copy);
-    } else if constant_rgx.is_match(token_content) {
-        return Token::Constant(content_copy);
-    } else if identifier_rgx.is_match(token_content) {
-        return Token::Identifier(content_copy);
+/**
+ * Splits an integer literal into its radix, digits and suffix.
+ * Supports decimal, octal (`017`), hex (`0x1F`) and binary (`0b101`, a GNU
+ * extension)
+ */
+fn split_integer_literal(content: &str) -> (u32, &str, &str) {
+    let lowercase = content.to_ascii_lowercase();
+    let (radix, prefix_length) = if lowercase.starts_with("0x") {
+        (16, 2)
+    } else if lowercase.starts_with("0b") {
+        (2, 2)
+    } else if content.starts_with('0') {
+        (8, 0)
     } else {
-        match token_content {
-            "(" => Some(Token::OpenParenthesis),
-            ")" => Some(Token::CloseParenthesis),
-            "{" => Some(Token::OpenBrace),
-            "}" => Some(Token::CloseBrace),
-            ";" => Some(Token::Semicolon),
-            "~" => Some(Token::Tilde),
-            "-" => Some(Token::Hyphen),
-            "+" => Some(Token::Plus),
-            "*" => Some(Token::Star),
-            "/" => Some(Token::Slash),
-            "%" => Some(Token::Modulo),
-            "&" => Some(Token::Ampersand),
-            "|" => Some(Token::Pipe),
-            "^" => Some(Token::Caret),
-            "<" => Some(Token::OpenAngleBracket),
-            ">" => Some(Token::CloseAngleBracket),
-            "<<" => Some(Token::DoubleOpenAngleBracket),
-            ">>" => Some(Token::DoubleCloseAngleBracket),
-            "!" => Some(Token::ExclamationPoint),
-            "=" => Some(Token::EqualSign),
-            "--" => {
-                panic!("-- should be one of the known lexical token types")
+        (10, 0)
+    };
+    let body = &content[prefix_length..];
+    let digit_count = body
+        .chars()
+        .take_while(|c| c.is_digit(radix.max(10)) || (radix == 16 && c.is_ascii_hexdigit()))
+        .count();
+    (radix, &body[..digit_count], &body[digit_count..])
+}
+
+/**
+ * Candidate types for a literal in the order the standard tries them.
+ * Returns None for suffixes that are not valid in C
+ */
+fn integer_literal_candidate_types(
+    suffix: &str,
+    is_decimal: bool,
+) -> Option<&'static [IntegerType]> {
+    use IntegerType::*;
+    let unsigned_stripped = suffix
+        .strip_prefix(['u', 'U'])
+        .or_else(|| suffix.strip_suffix(['u', 'U']));
+    let is_unsigned = unsigned_stripped.is_some();
+    let long_part = unsigned_stripped.unwrap_or(suffix);
+    let long_count = match long_part {
+        "" => 0,
+        "l" | "L" => 1,
+        "ll" | "LL" => 2,
+        _ => return None,
+    };
+    let candidates: &'static [IntegerType] = match (is_unsigned, long_count, is_decimal) {
+        (false, 0, true) => &[Int, Long, LongLong],
+        (false, 0, false) => &[
+            Int,
+            UnsignedInt,
+            Long,
+            UnsignedLong,
+            LongLong,
+            UnsignedLongLong,
+        ],
+        (true, 0, _) => &[UnsignedInt, UnsignedLong, UnsignedLongLong],
+        (false, 1, true) => &[Long, LongLong],
+        (false, 1, false) => &[Long, UnsignedLong, LongLong, UnsignedLongLong],
+        (true, 1, _) => &[UnsignedLong, UnsignedLongLong],
+        (false, _, true) => &[LongLong],
+        (false, _, false) => &[LongLong, UnsignedLongLong],
+        (true, _, _) => &[UnsignedLongLong],
+    };
+    Some(candidates)
+}
+
+/**
+ * Parses an integer constant with its suffix-derived type, or explains why
+ * the number cannot be one
+ */
+pub fn parse_integer_literal(content: &str) -> Result<Token, String> {
+    let decimal_points = content.matches('.').count();
+    if decimal_points > 1 {
+        return Err(String::from("too many decimal points in number"));
+    }
+    let (radix, digits, suffix) = split_integer_literal(content);
+    let is_exponent = |c: char| match radix {
+        16 => matches!(c, 'p' | 'P'),
+        _ => matches!(c, 'e' | 'E'),
+    };
+    if decimal_points == 1 || suffix.starts_with(is_exponent) {
+        return Err(String::from("floating constants are not supported"));
+    }
+    let radix_name = match radix {
+        16 => "hexadecimal",
+        8 => "octal",
+        2 => "binary",
+        _ => "decimal",
+    };
+    // octal and binary digits are scanned as decimal ones to point at the bad one
+    if let Some(invalid) = digits.chars().find(|c| !c.is_digit(radix)) {
+        return Err(format!(
+            "invalid digit '{invalid}' in {radix_name} constant"
+        ));
+    }
+    // decimal and octal digits include the leading one, only a `0x` or `0b`
+    // prefix can be left without any
+    if digits.is_empty() {
+        return Err(format!("{radix_name} constant {content} has no digits"));
+    }
+    let Some(candidates) = integer_literal_candidate_types(suffix, radix == 10) else {
+        return Err(format!("invalid suffix '{suffix}' on integer constant"));
+    };
+    let too_large = format!("integer constant {content} is too large for any integer type");
+    let Ok(value) = u64::from_str_radix(digits, radix) else {
+        return Err(too_large);
+    };
+    candidates
+        .iter()
+        .find(|ty| value <= ty.max_value())
+        .map(|ty| Token::Constant(value, *ty))
+        .ok_or(too_large)
+}
+
+/**
+ * Decodes the C escape sequences (simple, octal and hex) in the body of a
+ * character or string literal
+ */
+fn decode_escapes(body: &str) -> Result<Vec<u8>, String> {
+    let mut decoded = Vec::with_capacity(body.len());
+    let bytes = body.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'\\' {
+            decoded.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        let Some(&escape) = bytes.get(i + 1) else {
+            return Err(String::from("incomplete escape sequence"));
+        };
+        i += 2;
+        let value = match escape {
+            b'\'' | b'"' | b'?' | b'\\' => escape,
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b'f' => 0x0c,
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'v' => 0x0b,
+            b'0'..=b'7' => {
+                // up to three octal digits, the first one is already consumed
+                let mut value = (escape - b'0') as u32;
+                let mut digits = 1;
+                while digits < 3 && i < bytes.len() && (b'0'..=b'7').contains(&bytes[i]) {
+                    value = value * 8 + (bytes[i] - b'0') as u32;
+                    digits += 1;
+                    i += 1;
+                }
+                u8::try_from(value)
+                    .map_err(|_| format!("octal escape sequence \\{:o} out of range", value))?
+            }
+            b'x' => {
+                let digit_count = bytes[i..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_hexdigit())
+                    .count();
+                if digit_count == 0 {
+                    return Err(String::from("\\x used with no following hex digits"));
+                }
+                let digits = &body[i..i + digit_count];
+                i += digit_count;
+                u32::from_str_radix(digits, 16)
+                    .ok()
+                    .and_then(|value| u8::try_from(value).ok())
+                    .ok_or(format!("hex escape sequence \\x{digits} out of range"))?
             }
-            "&&" => Some(Token::DoubleAmpersand),
-            "||" => Some(Token::DoublePipe),
-            "==" => Some(Token::DoubleEqual),
-            "!=" => Some(Token::NotEqual),
-            "<=" => Some(Token::LessThanEqual),
-            ">=" => Some(Token::GreaterThanEqual),
-            _ => None,
+            _ => {
+                return Err(format!(
+                    "unknown escape sequence \\{}",
+                    body[i - 1..].chars().next().unwrap_or_default()
+                ));
+            }
+        };
+        decoded.push(value);
+    }
+    Ok(decoded)
+}
+
+/**
+ * Parses a quoted character or string literal. Returns None if the content
+ * does not start with a quote
+ */
+fn parse_quoted_literal(content: &str) -> Option<Result<Token, String>> {
+    let quote = content.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    if scan_quoted_literal(content) != (content.len(), true) {
+        return Some(Err(format!("missing terminating {quote} character")));
+    }
+    let body = &content[1..content.len() - 1];
+    Some(
+        decode_escapes(body).and_then(|bytes| match (quote, bytes.as_slice()) {
+            ('"', _) => Ok(Token::StringLiteral(bytes)),
+            (_, [byte]) => Ok(Token::CharLiteral(*byte)),
+            (_, []) => Err(String::from("empty character constant")),
+            (_, _) => Err(format!(
+                "multi-character character constant {content} is not supported"
+            )),
+        }),
+    )
+}
+
+impl Token {
+    /**
+     * Broad category of the token, as shown by `--lex`
+     */
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Token::Identifier(_) => "identifier",
+            Token::Constant(..) => "constant",
+            Token::CharLiteral(_) => "char-literal",
+            Token::StringLiteral(_) => "string-literal",
+            Token::Keyword(_) => "keyword",
+            Token::Newline => "newline",
+            Token::HeaderName(_) => "header-name",
+            Token::PpNumber(_) => "pp-number",
+            Token::Invalid(..) => "invalid",
+            _ => "punctuator",
         }
-        .expect(&format!(
-            "{token_content} should be one of the known lexical token types"
-        ))
     }
 }
 
-pub fn lex_contents(src_contents: String) -> Vec<Token> {
-    let mut tokens = Vec::new();
-    let starting_whitespace_pattern = Regex::new(r"^\s+").unwrap();
-    let next_token_pattern = Regex::new(r"^(\w+\b|--|==|!=|\|\||&&|<<|>>|<=|>=)").unwrap();
+/**
+ * One token per line as `file:line:col kind text`
+ */
+pub fn tokens_to_text(tokens: &[Spanned<Token>], source_map: &SourceMap) -> String {
+    tokens
+        .iter()
+        .map(|token| {
+            format!(
+                "{} {} {}\n",
+                source_map.location(token.span),
+                token.node.kind(),
+                token.node
+            )
+        })
+        .collect()
+}
 
-    let mut contents: String = src_contents.clone();
+fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
 
-    while !contents.is_empty() {
-        match starting_whitespace_pattern.find(&contents) {
-            Some(mat) => {
-                contents.drain(mat.range());
-            }
-            None => {
-                // get entire token
-                let mat = next_token_pattern.find(&contents);
-                let rng = match mat {
-                    Some(val) => val.range(),
-                    // contents not empty and first char is a boundary. Get the boundary
-                    None => 0..1,
-                };
-                // decice what to do with token
-                let token: String = contents.drain(rng).collect();
-                let classified_token = classify_token(&token);
-                tokens.push(classified_token);
-            }
+/**
+ * A JSON array with one object per token, for tools reading `--lex` output
+ */
+pub fn tokens_to_json(tokens: &[Spanned<Token>], source_map: &SourceMap) -> String {
+    let objects: Vec<String> = tokens
+        .iter()
+        .map(|token| {
+            let span = token.span;
+            format!(
+                "  {{\"kind\": {}, \"text\": {}, \"file\": {}, \"line\": {}, \"column\": {}, \"offset\": {}, \"length\": {}}}",
+                json_string(token.node.kind()),
+                json_string(&token.node.to_string()),
+                json_string(source_map.name(span.file)),
+                span.line,
+                span.column,
+                span.offset,
+                span.length
+            )
+        })
+        .collect();
+    match objects.is_empty() {
+        true => String::from("[]\n"),
+        false => format!("[\n{}\n]\n", objects.join(",\n")),
+    }
+}
+
+// punctuators (C17 6.4.6). `<: :> <% %> %: %:%:` are the digraphs
+const PUNCTUATORS: [(&str, Token); 54] = [
+    ("(", Token::OpenParenthesis),
+    (")", Token::CloseParenthesis),
+    ("{", Token::OpenBrace),
+    ("<%", Token::OpenBrace),
+    ("}", Token::CloseBrace),
+    ("%>", Token::CloseBrace),
+    ("[", Token::OpenSquareBracket),
+    ("<:", Token::OpenSquareBracket),
+    ("]", Token::CloseSquareBracket),
+    (":>", Token::CloseSquareBracket),
+    (";", Token::Semicolon),
+    (".", Token::Period),
+    ("->", Token::Arrow),
+    ("~", Token::Tilde),
+    ("-", Token::Hyphen),
+    ("+", Token::Plus),
+    ("*", Token::Star),
+    ("/", Token::Slash),
+    ("%", Token::Modulo),
+    ("&", Token::Ampersand),
+    ("|", Token::Pipe),
+    ("^", Token::Caret),
+    ("<", Token::OpenAngleBracket),
+    (">", Token::CloseAngleBracket),
+    ("<<", Token::DoubleOpenAngleBracket),
+    (">>", Token::DoubleCloseAngleBracket),
+    ("!", Token::ExclamationPoint),
+    ("=", Token::EqualSign),
+    ("++", Token::Increment),
+    ("--", Token::Decrement),
+    ("+=", Token::PlusEqual),
+    ("-=", Token::HyphenEqual),
+    ("*=", Token::StarEqual),
+    ("/=", Token::SlashEqual),
+    ("%=", Token::ModuloEqual),
+    ("&=", Token::AmpersandEqual),
+    ("|=", Token::PipeEqual),
+    ("^=", Token::CaretEqual),
+    ("<<=", Token::DoubleOpenAngleBracketEqual),
+    (">>=", Token::DoubleCloseAngleBracketEqual),
+    ("&&", Token::DoubleAmpersand),
+    ("||", Token::DoublePipe),
+    ("==", Token::DoubleEqual),
+    ("!=", Token::NotEqual),
+    ("<=", Token::LessThanEqual),
+    (">=", Token::GreaterThanEqual),
+    (",", Token::Comma),
+    ("?", Token::QuestionMark),
+    (":", Token::Colon),
+    ("...", Token::Ellipsis),
+    ("#", Token::Hash),
+    ("%:", Token::Hash),
+    ("##", Token::DoubleHash),
+    ("%:%:", Token::DoubleHash),
+];
+
+/**
+ * Scans the character or string literal at the start of `rest`. Returns its
+ * length including both quotes and whether it was terminated. Unterminated
+ * literals stop before the end of the line
+ */
+fn scan_quoted_literal(rest: &str) -> (usize, bool) {
+    let bytes = rest.as_bytes();
+    let quote = bytes[0];
+    let mut i = 1;
+    while i < bytes.len() && bytes[i] != b'\n' {
+        if bytes[i] == b'\\' {
+            i += 2;
+            continue;
+        }
+        if bytes[i] == quote {
+            return (i + 1, true);
+        }
+        i += 1;
+    }
+    (i.min(bytes.len()), false)
+}
+
+/**
+ * Preprocessing numbers start with a digit, or a period and a digit, and run
+ * over letters, digits, periods and signs after an exponent. `1.2.3` or `08`
+ * is one malformed number rather than several valid tokens
+ */
+fn scan_number(rest: &str) -> Option<usize> {
+    let bytes = rest.as_bytes();
+    match bytes {
+        [first, ..] if first.is_ascii_digit() => {}
+        [b'.', second, ..] if second.is_ascii_digit() => {}
+        _ => return None,
+    }
+    let mut length = 1;
+    while let Some(&byte) = bytes.get(length) {
+        let after_exponent = matches!(bytes[length - 1], b'e' | b'E' | b'p' | b'P');
+        if !(byte.is_ascii_alphanumeric()
+            || byte == b'_'
+            || byte == b'.'
+            || (after_exponent && matches!(byte, b'+' | b'-')))
+        {
+            break;
+        }
+        length += 1;
+    }
+    Some(length)
+}
+
+/**
+ * Words (identifiers and keywords) run until the next byte that cannot be
+ * part of an identifier. Numbers are scanned before this
+ */
+fn scan_word(rest: &str) -> Option<usize> {
+    Some(
+        rest.bytes()
+            .take_while(|b| b.is_ascii_alphanumeric() || *b == b'_')
+            .count(),
+    )
+}
+
+/**
+ * Length of the `<...>` header name at the start of `rest` if the line so far
+ * is `#include`. Anywhere else `<` is an operator
+ */
+fn header_name_length(tokens: &[Spanned<Token>], rest: &str) -> Option<usize> {
+    let line_start = tokens
+        .iter()
+        .rposition(|token| token.node == Token::Newline)
+        .map_or(0, |index| index + 1);
+    match &tokens[line_start..] {
+        [hash, include]
+            if hash.node == Token::Hash
+                && include.node == Token::Identifier(String::from("include"))
+                && rest.starts_with('<') =>
+        {
+            let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+            line.find('>').map(|index| index + 1)
         }
+        _ => None,
+    }
+}
+
+/**
+ * The C token specification. Lines ending in a backslash are spliced first
+ * (translation phase 2) and comments only ever separate tokens
+ */
+fn c_lexer(number: fn(&str) -> Result<Token, String>) -> Lexer<Token> {
+    Lexer::new()
+        .keywords(KEYWORDS.map(|(spelling, keyword)| (spelling, Token::Keyword(keyword))))
+        .punctuators(PUNCTUATORS)
+        .rule(
+            |rest| {
+                rest.starts_with(['\'', '"'])
+                    .then(|| scan_quoted_literal(rest).0)
+            },
+            |content| parse_quoted_literal(content).expect("literal starts with a quote"),
+        )
+        .rule(scan_number, number)
+        .rule(scan_word, |content| {
+            Ok(Token::Identifier(content.to_string()))
+        })
+        .line_comment("//")
+        .block_comment("/*", "*/")
+        .splice_lines()
+}
+
+/**
+ * Lexes source that does not go through the preprocessor, numbers become
+ * constants right away. The compiler always preprocesses, this is for tests
+ * of the stages after it
+ */
+#[cfg(test)]
+pub fn lex_contents(src_contents: String) -> Result<Vec<Spanned<Token>>, Vec<LexError>> {
+    c_lexer(parse_integer_literal).lex(&src_contents)
+}
+
+/**
+ * Lexes for the preprocessor, which needs to know where each logical line
+ * ends. A `Token::Newline` is emitted at the end of every logical line.
+ * Numbers keep their spelling for `#` and `##` and text that is not a token
+ * becomes a `Token::Invalid`, both are only checked once they are outside of
+ * skipped groups
+ */
+pub fn lex_lines(src_contents: &str) -> Result<Vec<Spanned<Token>>, Vec<LexError>> {
+    c_lexer(|content| Ok(Token::PpNumber(content.to_string())))
+        .contextual_rule(header_name_length, |content| {
+            Ok(Token::HeaderName(content[1..content.len() - 1].to_string()))
+        })
+        .newline_token(Token::Newline)
+        .invalid_token(|text, message| Token::Invalid(text.to_string(), message))
+        .lex(src_contents)
+}
+
+/**
+ * The token the parser sees for a token left after preprocessing, or the
+ * error that it is not one
+ */
+pub fn parser_token(token: Token) -> Result<Token, String> {
+    match token {
+        Token::PpNumber(spelling) => parse_integer_literal(&spelling),
+        Token::Invalid(_, message) => Err(message),
+        token => Ok(token),
     }
-    return tokens;
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::span::Span;
+
+    fn lex_errors(src: &str) -> Vec<String> {
+        lex_contents(src.to_string())
+            .unwrap_err()
+            .iter()
+            .map(LexError::to_string)
+            .collect()
+    }
+
+    // the token `content` lexes to on its own, or the first error
+    fn classify_token(content: &str) -> Result<Token, String> {
+        match lex_contents(content.to_string()) {
+            Ok(tokens) => Ok(tokens[0].node.clone()),
+            Err(errors) => Err(errors[0].message.clone()),
+        }
+    }
+
+    fn lex_kinds(src: &str) -> Vec<Token> {
+        lex_contents(src.to_string())
+            .unwrap()
+            .into_iter()
+            .map(|token| token.node)
+            .collect()
+    }
 
     #[test]
     fn test_classification() {
         macro_rules! test_classification {
             ($raw_content:literal, $expected_token_type:expr) => {
-                assert_eq!(classify_token($raw_content), $expected_token_type);
+                assert_eq!(classify_token($raw_content), Ok($expected_token_type));
             };
         }
 
-        test_classification!("int", Token::Keyword(String::from("int")));
+        test_classification!("int", Token::Keyword(Keyword::Int));
+        test_classification!("while", Token::Keyword(Keyword::While));
+        test_classification!("_Static_assert", Token::Keyword(Keyword::StaticAssert));
+        test_classification!(
+            "_static_assert",
+            Token::Identifier(String::from("_static_assert"))
+        );
         test_classification!("main", Token::Identifier("main".to_string()));
-        test_classification!("2", Token::Constant("2".to_string()));
+        test_classification!("2", Token::Constant(2, IntegerType::Int));
         test_classification!("(", Token::OpenParenthesis);
         test_classification!(")", Token::CloseParenthesis);
         test_classification!("{", Token::OpenBrace);
@@ -143,27 +828,447 @@ mod tests {
     }
 
     #[test]
-    #[should_panic = "123bar should be one of the known lexical token types"]
-    fn panic_for_bad_variable() {
-        classify_token("123bar");
+    fn print_tokens_as_text_and_json() {
+        let source_map = SourceMap::new("main.c");
+        let tokens = lex_contents(String::from("int x;\n  \"a\\\"b\"")).unwrap();
+        assert_eq!(
+            tokens_to_text(&tokens, &source_map),
+            "main.c:1:1 keyword int\n\
+             main.c:1:5 identifier x\n\
+             main.c:1:6 punctuator ;\n\
+             main.c:2:3 string-literal \"a\\\"b\"\n"
+        );
+        assert_eq!(
+            tokens_to_json(&tokens[2..], &source_map),
+            "[\n  {\"kind\": \"punctuator\", \"text\": \";\", \"file\": \"main.c\", \"line\": 1, \"column\": 6, \"offset\": 5, \"length\": 1},\n  \
+             {\"kind\": \"string-literal\", \"text\": \"\\\"a\\\\\\\"b\\\"\", \"file\": \"main.c\", \"line\": 2, \"column\": 3, \"offset\": 9, \"length\": 6}\n]\n"
+        );
+        assert_eq!(tokens_to_json(&[], &source_map), "[]\n");
+    }
+
+    #[test]
+    fn keyword_spellings_round_trip() {
+        for (spelling, keyword) in KEYWORDS {
+            assert_eq!(classify_token(spelling), Ok(Token::Keyword(keyword)));
+            assert_eq!(keyword.as_str(), spelling);
+        }
     }
 
     #[test]
-    #[should_panic = "-- should be one of the known lexical token types"]
-    fn recognize_minus_minus_above_double_negative() {
-        lex_contents(
+    fn error_for_bad_variable() {
+        assert_eq!(
+            lex_errors("123bar"),
+            vec!["invalid suffix 'bar' on integer constant at 1:1"]
+        );
+    }
+
+    #[test]
+    fn collect_every_error_in_one_pass() {
+        assert_eq!(
+            lex_errors("int 1x = @;\nreturn a ` $ 2;"),
+            vec![
+                "invalid suffix 'x' on integer constant at 1:5",
+                "stray '@' in program at 1:10",
+                "stray '`' in program at 2:10",
+                "stray '$' in program at 2:12",
+            ]
+        );
+    }
+
+    #[test]
+    fn integer_literal_radixes_and_suffixes() {
+        macro_rules! test_literal {
+            ($raw_content:literal, $value:expr, $ty:expr) => {
+                assert_eq!(
+                    classify_token($raw_content),
+                    Ok(Token::Constant($value, $ty))
+                );
+            };
+        }
+
+        test_literal!("0", 0, IntegerType::Int);
+        test_literal!("0x1F", 31, IntegerType::Int);
+        test_literal!("0XfF", 255, IntegerType::Int);
+        test_literal!("017", 15, IntegerType::Int);
+        test_literal!("0b101", 5, IntegerType::Int);
+        test_literal!("7u", 7, IntegerType::UnsignedInt);
+        test_literal!("7L", 7, IntegerType::Long);
+        test_literal!("7ul", 7, IntegerType::UnsignedLong);
+        test_literal!("7LU", 7, IntegerType::UnsignedLong);
+        test_literal!("7ll", 7, IntegerType::LongLong);
+        test_literal!("7ull", 7, IntegerType::UnsignedLongLong);
+        test_literal!("7LLu", 7, IntegerType::UnsignedLongLong);
+    }
+
+    #[test]
+    fn integer_literal_type_grows_with_value() {
+        // decimal literals never become unsigned without a suffix
+        assert_eq!(
+            classify_token("2147483648"),
+            Ok(Token::Constant(2147483648, IntegerType::Long))
+        );
+        // hex and octal literals try the unsigned type first
+        assert_eq!(
+            classify_token("0xFFFFFFFF"),
+            Ok(Token::Constant(0xFFFFFFFF, IntegerType::UnsignedInt))
+        );
+        assert_eq!(
+            classify_token("0xFFFFFFFFFFFFFFFF"),
+            Ok(Token::Constant(u64::MAX, IntegerType::UnsignedLong))
+        );
+        assert_eq!(
+            classify_token("4294967296u"),
+            Ok(Token::Constant(4294967296, IntegerType::UnsignedLong))
+        );
+    }
+
+    #[test]
+    fn reject_malformed_integer_literals() {
+        for (content, message) in [
+            ("08", "invalid digit '8' in octal constant"),
+            ("0779", "invalid digit '9' in octal constant"),
+            ("0b102", "invalid digit '2' in binary constant"),
+            ("0x", "hexadecimal constant 0x has no digits"),
+            ("0b", "binary constant 0b has no digits"),
+            ("1lul", "invalid suffix 'lul' on integer constant"),
+            ("1lL", "invalid suffix 'lL' on integer constant"),
+            ("12uu", "invalid suffix 'uu' on integer constant"),
+            ("0x1G", "invalid suffix 'G' on integer constant"),
+            ("1.2.3", "too many decimal points in number"),
+            ("1.5", "floating constants are not supported"),
+            (".5", "floating constants are not supported"),
+            ("1e+5", "floating constants are not supported"),
+            ("0x1p-2", "floating constants are not supported"),
+        ] {
+            assert_eq!(classify_token(content), Err(String::from(message)));
+        }
+    }
+
+    #[test]
+    fn malformed_numbers_are_one_token() {
+        assert_eq!(
+            lex_errors("return 1.2.3 + 08;"),
+            vec![
+                "too many decimal points in number at 1:8",
+                "invalid digit '8' in octal constant at 1:16",
+            ]
+        );
+        assert_eq!(
+            lex_kinds("1+x.y"),
+            vec![
+                Token::Constant(1, IntegerType::Int),
+                Token::Plus,
+                Token::Identifier(String::from("x")),
+                Token::Period,
+                Token::Identifier(String::from("y")),
+            ]
+        );
+    }
+
+    #[test]
+    fn error_on_integer_literal_overflow() {
+        assert_eq!(
+            lex_errors("int main(void) {\n                    return 18446744073709551616;\n}"),
+            vec!["integer constant 18446744073709551616 is too large for any integer type at 2:28"]
+        );
+    }
+
+    #[test]
+    fn decimal_literal_cannot_become_unsigned() {
+        assert_eq!(
+            lex_errors("9223372036854775808"),
+            vec!["integer constant 9223372036854775808 is too large for any integer type at 1:1"]
+        );
+    }
+
+    #[test]
+    fn decode_literal_escape_sequences() {
+        macro_rules! test_literal {
+            ($raw_content:literal, $expected_token_type:expr) => {
+                assert_eq!(classify_token($raw_content), Ok($expected_token_type));
+            };
+        }
+
+        test_literal!("'a'", Token::CharLiteral(b'a'));
+        test_literal!(r"'\n'", Token::CharLiteral(b'\n'));
+        test_literal!(r"'\''", Token::CharLiteral(b'\''));
+        test_literal!(r"'\0'", Token::CharLiteral(0));
+        test_literal!(r"'\177'", Token::CharLiteral(0o177));
+        test_literal!(r"'\xff'", Token::CharLiteral(0xff));
+        test_literal!(
+            r#""hello\tworld""#,
+            Token::StringLiteral(b"hello\tworld".to_vec())
+        );
+        test_literal!(
+            r#""\a\b\f\r\v\?\\\"""#,
+            Token::StringLiteral(b"\x07\x08\x0c\r\x0b?\\\"".to_vec())
+        );
+        // octal escapes stop after three digits, hex escapes don't
+        test_literal!(r#""\1234""#, Token::StringLiteral(b"S4".to_vec()));
+        test_literal!(r#""\x41g""#, Token::StringLiteral(b"Ag".to_vec()));
+        test_literal!(r#""""#, Token::StringLiteral(vec![]));
+    }
+
+    #[test]
+    fn reject_malformed_literals() {
+        macro_rules! test_rejection {
+            ($raw_content:literal, $message:literal) => {
+                assert_eq!(classify_token($raw_content), Err(String::from($message)));
+            };
+        }
+
+        test_rejection!("''", "empty character constant");
+        test_rejection!(
+            "'ab'",
+            "multi-character character constant 'ab' is not supported"
+        );
+        test_rejection!(r"'\q'", r"unknown escape sequence \q");
+        test_rejection!(r"'\x'", r"\x used with no following hex digits");
+        test_rejection!(r"'\x100'", r"hex escape sequence \x100 out of range");
+        test_rejection!(r"'\777'", r"octal escape sequence \777 out of range");
+        test_rejection!(r#""abc\""#, r#"missing terminating " character"#);
+    }
+
+    #[test]
+    fn lex_literals_in_program() {
+        let result = lex_kinds("return 'x' + \"a;b\";");
+        assert_eq!(
+            result,
+            vec![
+                Token::Keyword(Keyword::Return),
+                Token::CharLiteral(b'x'),
+                Token::Plus,
+                Token::StringLiteral(b"a;b".to_vec()),
+                Token::Semicolon,
+            ]
+        );
+    }
+
+    #[test]
+    fn error_on_unterminated_char_literal() {
+        assert_eq!(
+            lex_errors("return 'a;\n}"),
+            vec!["missing terminating ' character at 1:8"]
+        );
+    }
+
+    #[test]
+    fn skip_comments() {
+        let result = lex_kinds(
             "
-                int main() {
-                    return --2;
+                // line comment with int main() inside
+                int /* block */ main(/**/) { /* multi
+                   line *** comment */
+                    return \"// not a comment\"; // trailing
                 }
-            "
-            .to_string(),
+            ",
+        );
+
+        assert_eq!(
+            result,
+            vec![
+                Token::Keyword(Keyword::Int),
+                Token::Identifier("main".to_string()),
+                Token::OpenParenthesis,
+                Token::CloseParenthesis,
+                Token::OpenBrace,
+                Token::Keyword(Keyword::Return),
+                Token::StringLiteral(b"// not a comment".to_vec()),
+                Token::Semicolon,
+                Token::CloseBrace,
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_separate_tokens() {
+        assert_eq!(
+            lex_kinds("a/**/b"),
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::Identifier("b".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn splice_lines_ending_in_backslash() {
+        let result =
+            lex_contents("re\\\nturn 1; // comment \\\n continued\r\n+\\\r\n2".to_string())
+                .unwrap();
+        assert_eq!(
+            result,
+            vec![
+                Spanned::new(Token::Keyword(Keyword::Return), Span::new(0, 1, 1, 8)),
+                Spanned::new(Token::Constant(1, IntegerType::Int), Span::new(9, 2, 6, 1)),
+                Spanned::new(Token::Semicolon, Span::new(10, 2, 7, 1)),
+                Spanned::new(Token::Plus, Span::new(37, 4, 1, 1)),
+                Spanned::new(Token::Constant(2, IntegerType::Int), Span::new(41, 5, 1, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn error_on_unterminated_block_comment() {
+        assert_eq!(
+            lex_errors("int\n    /* never closed *\n/"),
+            vec!["unterminated comment at 2:5"]
+        );
+    }
+
+    #[test]
+    fn lex_lines_marks_logical_line_ends() {
+        let result: Vec<Token> = lex_lines("#define X \\\n 1 /* a\n b */\n\nX##X")
+            .unwrap()
+            .into_iter()
+            .map(|token| token.node)
+            .collect();
+        assert_eq!(
+            result,
+            vec![
+                Token::Hash,
+                Token::Identifier(String::from("define")),
+                Token::Identifier(String::from("X")),
+                Token::PpNumber(String::from("1")),
+                Token::Newline,
+                Token::Newline,
+                Token::Identifier(String::from("X")),
+                Token::DoubleHash,
+                Token::Identifier(String::from("X")),
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_lines_keeps_invalid_text() {
+        let result: Vec<Token> = lex_lines("don't @")
+            .unwrap()
+            .into_iter()
+            .map(|token| token.node)
+            .collect();
+        assert_eq!(
+            result,
+            vec![
+                Token::Identifier(String::from("don")),
+                Token::Invalid(
+                    String::from("'t @"),
+                    String::from("missing terminating ' character")
+                ),
+            ]
+        );
+        assert_eq!(
+            parser_token(Token::Invalid(
+                String::from("@"),
+                String::from("stray '@' in program")
+            )),
+            Err(String::from("stray '@' in program"))
+        );
+    }
+
+    #[test]
+    fn header_names_only_follow_include() {
+        let result: Vec<Token> = lex_lines("#include <sys/types.h>\na < b > c")
+            .unwrap()
+            .into_iter()
+            .map(|token| token.node)
+            .collect();
+        assert_eq!(
+            result,
+            vec![
+                Token::Hash,
+                Token::Identifier(String::from("include")),
+                Token::HeaderName(String::from("sys/types.h")),
+                Token::Newline,
+                Token::Identifier(String::from("a")),
+                Token::OpenAngleBracket,
+                Token::Identifier(String::from("b")),
+                Token::CloseAngleBracket,
+                Token::Identifier(String::from("c")),
+            ]
+        );
+    }
+
+    #[test]
+    fn recognize_minus_minus_above_double_negative() {
+        assert_eq!(
+            lex_kinds("return --2;"),
+            vec![
+                Token::Keyword(Keyword::Return),
+                Token::Decrement,
+                Token::Constant(2, IntegerType::Int),
+                Token::Semicolon,
+            ]
+        );
+    }
+
+    #[test]
+    fn punctuators_use_maximal_munch() {
+        assert_eq!(
+            lex_kinds("a+++++b x<<=y>>=z p->q.r ... .. [1] ?: ;"),
+            vec![
+                Token::Identifier(String::from("a")),
+                Token::Increment,
+                Token::Increment,
+                Token::Plus,
+                Token::Identifier(String::from("b")),
+                Token::Identifier(String::from("x")),
+                Token::DoubleOpenAngleBracketEqual,
+                Token::Identifier(String::from("y")),
+                Token::DoubleCloseAngleBracketEqual,
+                Token::Identifier(String::from("z")),
+                Token::Identifier(String::from("p")),
+                Token::Arrow,
+                Token::Identifier(String::from("q")),
+                Token::Period,
+                Token::Identifier(String::from("r")),
+                Token::Ellipsis,
+                Token::Period,
+                Token::Period,
+                Token::OpenSquareBracket,
+                Token::Constant(1, IntegerType::Int),
+                Token::CloseSquareBracket,
+                Token::QuestionMark,
+                Token::Colon,
+                Token::Semicolon,
+            ]
+        );
+        assert_eq!(
+            lex_kinds("+= -= *= /= %= &= |= ^= -> --"),
+            vec![
+                Token::PlusEqual,
+                Token::HyphenEqual,
+                Token::StarEqual,
+                Token::SlashEqual,
+                Token::ModuloEqual,
+                Token::AmpersandEqual,
+                Token::PipeEqual,
+                Token::CaretEqual,
+                Token::Arrow,
+                Token::Decrement,
+            ]
+        );
+    }
+
+    #[test]
+    fn digraphs() {
+        assert_eq!(
+            lex_kinds("<: :> <% %> %: %:%: %:%"),
+            vec![
+                Token::OpenSquareBracket,
+                Token::CloseSquareBracket,
+                Token::OpenBrace,
+                Token::CloseBrace,
+                Token::Hash,
+                Token::DoubleHash,
+                Token::Hash,
+                Token::Modulo,
+            ]
         );
     }
 
     #[test]
     fn lex_simple_program() {
-        let result = lex_contents(
+        let result = lex_kinds(
             "
 
                 int main() {
@@ -171,20 +1276,19 @@ mod tests {
                 }
 
                 
-            "
-            .to_string(),
+            ",
         );
 
         assert_eq!(
             result,
             Vec::from([
-                Token::Keyword(String::from("int")),
+                Token::Keyword(Keyword::Int),
                 Token::Identifier("main".to_string()),
                 Token::OpenParenthesis,
                 Token::CloseParenthesis,
                 Token::OpenBrace,
-                Token::Keyword(String::from("return")),
-                Token::Constant("2".to_string()),
+                Token::Keyword(Keyword::Return),
+                Token::Constant(2, IntegerType::Int),
                 Token::Semicolon,
                 Token::CloseBrace,
             ])
@@ -193,33 +1297,32 @@ mod tests {
 
     #[test]
     fn should_lex_nested_unary_ops() {
-        let result = lex_contents(
+        let result = lex_kinds(
             "
 
                 int main() {
                     return (~(-(-2)));
                 }
 
-            "
-            .to_string(),
+            ",
         );
 
         assert_eq!(
             result,
             Vec::from([
-                Token::Keyword(String::from("int")),
+                Token::Keyword(Keyword::Int),
                 Token::Identifier("main".to_string()),
                 Token::OpenParenthesis,
                 Token::CloseParenthesis,
                 Token::OpenBrace,
-                Token::Keyword(String::from("return")),
+                Token::Keyword(Keyword::Return),
                 Token::OpenParenthesis,
                 Token::Tilde,
                 Token::OpenParenthesis,
                 Token::Hyphen,
                 Token::OpenParenthesis,
                 Token::Hyphen,
-                Token::Constant("2".to_string()),
+                Token::Constant(2, IntegerType::Int),
                 Token::CloseParenthesis,
                 Token::CloseParenthesis,
                 Token::CloseParenthesis,
@@ -231,46 +1334,65 @@ mod tests {
 
     #[test]
     fn should_lex_multiple_binary_ops() {
-        let result = lex_contents(
+        let result = lex_kinds(
             "
 
                 int main() {
                     return (1 + 2) * (4 - 3) / (3 % 2);
                 }
 
-            "
-            .to_string(),
+            ",
         );
 
         assert_eq!(
             result,
             Vec::from([
-                Token::Keyword(String::from("int")),
+                Token::Keyword(Keyword::Int),
                 Token::Identifier("main".to_string()),
                 Token::OpenParenthesis,
                 Token::CloseParenthesis,
                 Token::OpenBrace,
-                Token::Keyword(String::from("return")),
+                Token::Keyword(Keyword::Return),
                 Token::OpenParenthesis,
-                Token::Constant(String::from("1")),
+                Token::Constant(1, IntegerType::Int),
                 Token::Plus,
-                Token::Constant(String::from("2")),
+                Token::Constant(2, IntegerType::Int),
                 Token::CloseParenthesis,
                 Token::Star,
                 Token::OpenParenthesis,
-                Token::Constant(String::from("4")),
+                Token::Constant(4, IntegerType::Int),
                 Token::Hyphen,
-                Token::Constant(String::from("3")),
+                Token::Constant(3, IntegerType::Int),
                 Token::CloseParenthesis,
                 Token::Slash,
                 Token::OpenParenthesis,
-                Token::Constant(String::from("3")),
+                Token::Constant(3, IntegerType::Int),
                 Token::Modulo,
-                Token::Constant(String::from("2")),
+                Token::Constant(2, IntegerType::Int),
                 Token::CloseParenthesis,
                 Token::Semicolon,
                 Token::CloseBrace,
             ])
         )
     }
+
+    #[test]
+    fn tokens_carry_source_spans() {
+        let result = lex_contents("int main() {\n    return 2;\n}".to_string()).unwrap();
+        let spans: Vec<Span> = result.iter().map(|token| token.span).collect();
+        assert_eq!(
+            spans,
+            vec![
+                Span::new(0, 1, 1, 3),
+                Span::new(4, 1, 5, 4),
+                Span::new(8, 1, 9, 1),
+                Span::new(9, 1, 10, 1),
+                Span::new(11, 1, 12, 1),
+                Span::new(17, 2, 5, 6),
+                Span::new(24, 2, 12, 1),
+                Span::new(25, 2, 13, 1),
+                Span::new(27, 3, 1, 1),
+            ]
+        );
+    }
 }
@@ -1,18 +1,63 @@
 mod ast;
+#[cfg(feature = "dump-ast")]
+mod dump;
 mod from_lexical;
+mod label_loops;
 pub mod lexer;
+mod preprocess;
+mod resolve;
+pub mod to_source;
 pub mod to_tacky;
+mod visit;
 
+use crate::core::diagnostic::Diagnostic;
 use crate::core::parser;
+use crate::core::source_map::SourceMap;
+use crate::core::span::Spanned;
 
 use std::process;
 
-pub fn process_program(input: String, lex_only: bool) -> ast::Program {
-    let tokens = lexer::lex_contents(input);
-    if lex_only {
-        // stop here & mark as success if we only want lexing
-        process::exit(0);
+/**
+ * Prints diagnostics to stderr and exits with a failure status
+ */
+fn report_and_exit(diagnostics: Vec<Diagnostic>, source_map: &SourceMap) -> ! {
+    for diagnostic in diagnostics {
+        eprintln!("{}", diagnostic.render(source_map));
     }
+    process::exit(1);
+}
+
+/**
+ * Preprocesses and lexes a source file. The source map is needed to report
+ * locations in later stages
+ */
+pub fn preprocess_program(
+    input: String,
+    file_name: &str,
+    include_paths: &[String],
+) -> (Vec<Spanned<lexer::Token>>, SourceMap) {
+    let mut source_map = SourceMap::new(file_name);
+    match preprocess::preprocess(&input, file_name, include_paths, &mut source_map) {
+        Ok(tokens) => (tokens, source_map),
+        Err(errors) => report_and_exit(
+            errors.into_iter().map(Diagnostic::from).collect(),
+            &source_map,
+        ),
+    }
+}
+
+pub fn parse_program(tokens: Vec<Spanned<lexer::Token>>, source_map: &SourceMap) -> ast::Program {
     let mut parser = parser::Parser::new(tokens);
     from_lexical::parse_program(&mut parser)
+        .unwrap_or_else(|diagnostics| report_and_exit(diagnostics, source_map))
+}
+
+/**
+ * Runs the semantic passes over a parsed program: variable resolution, then
+ * labeling of loops, switches and their cases
+ */
+pub fn validate_program(program: ast::Program, source_map: &SourceMap) -> ast::Program {
+    resolve::resolve_program(program)
+        .and_then(label_loops::label_loops)
+        .unwrap_or_else(|diagnostics| report_and_exit(diagnostics, source_map))
 }
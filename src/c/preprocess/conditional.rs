@@ -0,0 +1,160 @@
+use crate::core::lexer::LexError;
+use crate::core::span::Span;
+
+/**
+ * One `#if`/`#ifdef`/`#ifndef` ... `#endif` group that is still open
+ */
+#[derive(Debug)]
+struct Conditional {
+    span: Span,
+    // whether the enclosing group is being emitted, nothing nested in a
+    // skipped group is ever emitted
+    parent_active: bool,
+    // whether one of the branches so far was taken
+    taken: bool,
+    // whether the current branch is being emitted
+    active: bool,
+    seen_else: bool,
+}
+
+/**
+ * Tracks nesting of conditional directives within one file
+ */
+#[derive(Debug, Default)]
+pub(super) struct ConditionalStack {
+    stack: Vec<Conditional>,
+}
+
+impl ConditionalStack {
+    /**
+     * Whether lines at this point should be emitted
+     */
+    pub(super) fn active(&self) -> bool {
+        self.stack
+            .last()
+            .is_none_or(|conditional| conditional.active)
+    }
+
+    /**
+     * Opens a new group. The condition is only evaluated when the enclosing
+     * group is active, so skipped groups never evaluate expressions
+     */
+    pub(super) fn push(&mut self, span: Span, condition: impl FnOnce() -> bool) {
+        let parent_active = self.active();
+        let active = parent_active && condition();
+        self.stack.push(Conditional {
+            span,
+            parent_active,
+            taken: active,
+            active,
+            seen_else: false,
+        });
+    }
+
+    pub(super) fn elif(
+        &mut self,
+        span: Span,
+        condition: impl FnOnce() -> bool,
+    ) -> Result<(), LexError> {
+        let Some(conditional) = self.stack.last_mut() else {
+            return Err(LexError::new("#elif without #if", span));
+        };
+        if conditional.seen_else {
+            return Err(LexError::new("#elif after #else", span));
+        }
+        conditional.active = conditional.parent_active && !conditional.taken && condition();
+        conditional.taken |= conditional.active;
+        Ok(())
+    }
+
+    pub(super) fn else_branch(&mut self, span: Span) -> Result<(), LexError> {
+        let Some(conditional) = self.stack.last_mut() else {
+            return Err(LexError::new("#else without #if", span));
+        };
+        if conditional.seen_else {
+            return Err(LexError::new("#else after #else", span));
+        }
+        conditional.seen_else = true;
+        conditional.active = conditional.parent_active && !conditional.taken;
+        conditional.taken = true;
+        Ok(())
+    }
+
+    pub(super) fn endif(&mut self, span: Span) -> Result<(), LexError> {
+        match self.stack.pop() {
+            Some(_) => Ok(()),
+            None => Err(LexError::new("#endif without #if", span)),
+        }
+    }
+
+    /**
+     * Every group must be closed in the file that opened it. Returns an error
+     * for each group left open
+     */
+    pub(super) fn finish(self) -> Vec<LexError> {
+        self.stack
+            .into_iter()
+            .map(|conditional| {
+                LexError::new("unterminated conditional directive", conditional.span)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_first_true_branch_is_active() {
+        let mut conditionals = ConditionalStack::default();
+        conditionals.push(Span::default(), || false);
+        assert!(!conditionals.active());
+        conditionals.elif(Span::default(), || true).unwrap();
+        assert!(conditionals.active());
+        conditionals.elif(Span::default(), || true).unwrap();
+        assert!(!conditionals.active());
+        conditionals.else_branch(Span::default()).unwrap();
+        assert!(!conditionals.active());
+        conditionals.endif(Span::default()).unwrap();
+        assert!(conditionals.active());
+        assert_eq!(conditionals.finish(), vec![]);
+    }
+
+    #[test]
+    fn nested_groups_in_skipped_group_are_not_evaluated() {
+        let mut conditionals = ConditionalStack::default();
+        conditionals.push(Span::default(), || false);
+        conditionals.push(Span::default(), || panic!("evaluated"));
+        conditionals.else_branch(Span::default()).unwrap();
+        assert!(!conditionals.active());
+        conditionals.endif(Span::default()).unwrap();
+        conditionals.endif(Span::default()).unwrap();
+        assert!(conditionals.active());
+    }
+
+    #[test]
+    fn else_after_else() {
+        let mut conditionals = ConditionalStack::default();
+        conditionals.push(Span::default(), || true);
+        conditionals.else_branch(Span::default()).unwrap();
+        assert_eq!(
+            conditionals.else_branch(Span::new(0, 1, 1, 5)),
+            Err(LexError::new("#else after #else", Span::new(0, 1, 1, 5)))
+        );
+    }
+
+    #[test]
+    fn every_open_group_is_unterminated() {
+        let mut conditionals = ConditionalStack::default();
+        conditionals.push(Span::new(0, 1, 1, 1), || true);
+        conditionals.push(Span::new(6, 2, 1, 1), || true);
+        assert_eq!(
+            conditionals.finish(),
+            vec![
+                LexError::new("unterminated conditional directive", Span::new(0, 1, 1, 1)),
+                LexError::new("unterminated conditional directive", Span::new(6, 2, 1, 1)),
+            ]
+        );
+    }
+}
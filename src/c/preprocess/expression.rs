@@ -0,0 +1,451 @@
+use super::{PpToken, Preprocessor, identifier_name};
+use crate::c::lexer::{self, IntegerType, Token};
+use crate::core::lexer::LexError;
+use crate::core::span::Span;
+
+/**
+ * Values in `#if` expressions have type intmax_t or uintmax_t
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value {
+    Signed(i64),
+    Unsigned(u64),
+}
+
+impl Value {
+    fn is_true(self) -> bool {
+        self != Value::Signed(0) && self != Value::Unsigned(0)
+    }
+
+    fn bits(self) -> u64 {
+        match self {
+            Value::Signed(v) => v as u64,
+            Value::Unsigned(v) => v,
+        }
+    }
+
+    fn from_bool(b: bool) -> Value {
+        Value::Signed(b as i64)
+    }
+}
+
+fn binary_operator_precedence(token: &Token) -> Option<u8> {
+    match token {
+        Token::Star | Token::Slash | Token::Modulo => Some(10),
+        Token::Plus | Token::Hyphen => Some(9),
+        Token::DoubleOpenAngleBracket | Token::DoubleCloseAngleBracket => Some(8),
+        Token::OpenAngleBracket
+        | Token::CloseAngleBracket
+        | Token::LessThanEqual
+        | Token::GreaterThanEqual => Some(7),
+        Token::DoubleEqual | Token::NotEqual => Some(6),
+        Token::Ampersand => Some(5),
+        Token::Caret => Some(4),
+        Token::Pipe => Some(3),
+        Token::DoubleAmpersand => Some(2),
+        Token::DoublePipe => Some(1),
+        _ => None,
+    }
+}
+
+/**
+ * Precedence climbing evaluator over a fully expanded `#if` line. `live` is
+ * false inside the unevaluated operand of `&&`, `||` and `?:`, where
+ * division by zero is not an error
+ */
+struct Evaluator<'a> {
+    tokens: &'a [PpToken],
+    cursor: usize,
+    directive_span: Span,
+}
+
+impl Evaluator<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.cursor).map(|token| &token.token)
+    }
+
+    fn span(&self) -> Span {
+        self.tokens
+            .get(self.cursor)
+            .map_or(self.directive_span, |token| token.span)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), LexError> {
+        if self.peek() != Some(&expected) {
+            let found = match self.tokens.get(self.cursor) {
+                Some(token) => format!("'{}'", token.spelling),
+                None => String::from("end of line"),
+            };
+            return Err(LexError::new(
+                format!("expected '{expected}' in #if expression before {found}"),
+                self.span(),
+            ));
+        }
+        self.cursor += 1;
+        Ok(())
+    }
+
+    fn conditional(&mut self, live: bool) -> Result<Value, LexError> {
+        let condition = self.binary(0, live)?;
+        if self.peek() != Some(&Token::QuestionMark) {
+            return Ok(condition);
+        }
+        self.cursor += 1;
+        let taken = condition.is_true();
+        let then = self.conditional(live && taken)?;
+        self.expect(Token::Colon)?;
+        let otherwise = self.conditional(live && !taken)?;
+        let (then, otherwise) = convert(then, otherwise);
+        Ok(if taken { then } else { otherwise })
+    }
+
+    fn binary(&mut self, min_precedence: u8, live: bool) -> Result<Value, LexError> {
+        let mut lhs = self.unary(live)?;
+        while let Some(operator) = self.peek().cloned()
+            && let Some(precedence) = binary_operator_precedence(&operator)
+            && precedence > min_precedence
+        {
+            let span = self.span();
+            self.cursor += 1;
+            lhs = match operator {
+                Token::DoubleAmpersand => {
+                    let rhs = self.binary(precedence, live && lhs.is_true())?;
+                    Value::from_bool(lhs.is_true() && rhs.is_true())
+                }
+                Token::DoublePipe => {
+                    let rhs = self.binary(precedence, live && !lhs.is_true())?;
+                    Value::from_bool(lhs.is_true() || rhs.is_true())
+                }
+                _ => {
+                    let rhs = self.binary(precedence, live)?;
+                    apply_binary(&operator, lhs, rhs, live, span)?
+                }
+            };
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self, live: bool) -> Result<Value, LexError> {
+        let span = self.span();
+        let Some(token) = self.tokens.get(self.cursor) else {
+            return Err(LexError::new("expected value in #if expression", span));
+        };
+        self.cursor += 1;
+        let value = match &token.token {
+            Token::PpNumber(spelling) => match lexer::parse_integer_literal(spelling) {
+                Ok(Token::Constant(value, ty)) => match ty {
+                    IntegerType::UnsignedInt
+                    | IntegerType::UnsignedLong
+                    | IntegerType::UnsignedLongLong => Value::Unsigned(value),
+                    _ if value > i64::MAX as u64 => Value::Unsigned(value),
+                    _ => Value::Signed(value as i64),
+                },
+                Ok(_) => unreachable!("integer literals lex to constants"),
+                Err(message) => return Err(LexError::new(message, span)),
+            },
+            Token::CharLiteral(value) => Value::Signed(*value as i8 as i64),
+            Token::OpenParenthesis => {
+                let value = self.conditional(live)?;
+                self.expect(Token::CloseParenthesis)?;
+                value
+            }
+            Token::Plus => self.unary(live)?,
+            Token::Hyphen => match self.unary(live)? {
+                Value::Signed(v) => Value::Signed(v.wrapping_neg()),
+                Value::Unsigned(v) => Value::Unsigned(v.wrapping_neg()),
+            },
+            Token::Tilde => match self.unary(live)? {
+                Value::Signed(v) => Value::Signed(!v),
+                Value::Unsigned(v) => Value::Unsigned(!v),
+            },
+            Token::ExclamationPoint => Value::from_bool(!self.unary(live)?.is_true()),
+            Token::Invalid(_, message) => return Err(LexError::new(message.clone(), span)),
+            _ => {
+                return Err(LexError::new(
+                    format!("invalid token \"{}\" in #if expression", token.spelling),
+                    span,
+                ));
+            }
+        };
+        Ok(value)
+    }
+}
+
+/**
+ * The usual arithmetic conversions: if either operand is unsigned both are
+ */
+fn convert(lhs: Value, rhs: Value) -> (Value, Value) {
+    match (lhs, rhs) {
+        (Value::Signed(_), Value::Signed(_)) => (lhs, rhs),
+        _ => (Value::Unsigned(lhs.bits()), Value::Unsigned(rhs.bits())),
+    }
+}
+
+fn apply_binary(
+    operator: &Token,
+    lhs: Value,
+    rhs: Value,
+    live: bool,
+    span: Span,
+) -> Result<Value, LexError> {
+    // shifts keep the type of the left operand
+    let shift = (rhs.bits() & 63) as u32;
+    match operator {
+        Token::DoubleOpenAngleBracket => {
+            return Ok(match lhs {
+                Value::Signed(v) => Value::Signed(v.wrapping_shl(shift)),
+                Value::Unsigned(v) => Value::Unsigned(v.wrapping_shl(shift)),
+            });
+        }
+        Token::DoubleCloseAngleBracket => {
+            return Ok(match lhs {
+                Value::Signed(v) => Value::Signed(v.wrapping_shr(shift)),
+                Value::Unsigned(v) => Value::Unsigned(v.wrapping_shr(shift)),
+            });
+        }
+        _ => {}
+    }
+    if matches!(operator, Token::Slash | Token::Modulo) && !rhs.is_true() {
+        if live {
+            return Err(LexError::new("division by zero in #if expression", span));
+        }
+        return Ok(Value::Signed(0));
+    }
+    let value = match convert(lhs, rhs) {
+        (Value::Signed(a), Value::Signed(b)) => match operator {
+            Token::Star => Value::Signed(a.wrapping_mul(b)),
+            Token::Slash => Value::Signed(a.wrapping_div(b)),
+            Token::Modulo => Value::Signed(a.wrapping_rem(b)),
+            Token::Plus => Value::Signed(a.wrapping_add(b)),
+            Token::Hyphen => Value::Signed(a.wrapping_sub(b)),
+            Token::Ampersand => Value::Signed(a & b),
+            Token::Caret => Value::Signed(a ^ b),
+            Token::Pipe => Value::Signed(a | b),
+            _ => compare(operator, a, b),
+        },
+        (a, b) => {
+            let (a, b) = (a.bits(), b.bits());
+            match operator {
+                Token::Star => Value::Unsigned(a.wrapping_mul(b)),
+                Token::Slash => Value::Unsigned(a / b),
+                Token::Modulo => Value::Unsigned(a % b),
+                Token::Plus => Value::Unsigned(a.wrapping_add(b)),
+                Token::Hyphen => Value::Unsigned(a.wrapping_sub(b)),
+                Token::Ampersand => Value::Unsigned(a & b),
+                Token::Caret => Value::Unsigned(a ^ b),
+                Token::Pipe => Value::Unsigned(a | b),
+                _ => compare(operator, a, b),
+            }
+        }
+    };
+    Ok(value)
+}
+
+fn compare<T: PartialOrd>(operator: &Token, a: T, b: T) -> Value {
+    Value::from_bool(match operator {
+        Token::OpenAngleBracket => a < b,
+        Token::CloseAngleBracket => a > b,
+        Token::LessThanEqual => a <= b,
+        Token::GreaterThanEqual => a >= b,
+        Token::DoubleEqual => a == b,
+        Token::NotEqual => a != b,
+        _ => unreachable!("{operator:?} is not a binary operator"),
+    })
+}
+
+impl Preprocessor<'_> {
+    /**
+     * Replaces `defined X` and `defined(X)` with 1 or 0. This has to happen
+     * before macro expansion so the operand is not expanded
+     */
+    fn replace_defined(&self, tokens: &[PpToken]) -> Result<Vec<PpToken>, LexError> {
+        let mut result: Vec<PpToken> = vec![];
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = &tokens[i];
+            if identifier_name(&token.token) != Some("defined") {
+                result.push(token.clone());
+                i += 1;
+                continue;
+            }
+            let parenthesized =
+                tokens.get(i + 1).map(|t| &t.token) == Some(&Token::OpenParenthesis);
+            let name_index = if parenthesized { i + 2 } else { i + 1 };
+            let Some(name) = tokens
+                .get(name_index)
+                .and_then(|t| identifier_name(&t.token))
+            else {
+                return Err(LexError::new(
+                    "macro name missing after defined",
+                    token.span,
+                ));
+            };
+            if parenthesized
+                && tokens.get(name_index + 1).map(|t| &t.token) != Some(&Token::CloseParenthesis)
+            {
+                return Err(LexError::new("missing ) after defined", token.span));
+            }
+            let value = self.macros.contains_key(name) as u64;
+            result.push(PpToken::number(value, token.span));
+            i = name_index + 1 + parenthesized as usize;
+        }
+        Ok(result)
+    }
+
+    /**
+     * Evaluates the controlling expression of `#if` and `#elif`. Identifiers
+     * left after macro expansion evaluate to 0. An invalid expression is
+     * reported and counts as false
+     */
+    pub(super) fn evaluate_condition(&mut self, tokens: &[PpToken], directive_span: Span) -> bool {
+        match self.try_evaluate_condition(tokens, directive_span) {
+            Ok(value) => value,
+            Err(error) => {
+                self.errors.push(error);
+                false
+            }
+        }
+    }
+
+    fn try_evaluate_condition(
+        &mut self,
+        tokens: &[PpToken],
+        directive_span: Span,
+    ) -> Result<bool, LexError> {
+        if tokens.is_empty() {
+            return Err(LexError::new("#if with no expression", directive_span));
+        }
+        let replaced = self.replace_defined(tokens)?;
+        let tokens: Vec<PpToken> = self
+            .expand(replaced)
+            .into_iter()
+            .map(|token| match identifier_name(&token.token) {
+                Some(_) => PpToken::number(0, token.span),
+                None => token,
+            })
+            .collect();
+        let mut evaluator = Evaluator {
+            tokens: &tokens,
+            cursor: 0,
+            directive_span,
+        };
+        let value = evaluator.conditional(true)?;
+        if let Some(token) = tokens.get(evaluator.cursor) {
+            return Err(LexError::new(
+                format!("unexpected \"{}\" in #if expression", token.spelling),
+                token.span,
+            ));
+        }
+        Ok(value.is_true())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{preprocess_errors, preprocess_kinds};
+    use crate::c::lexer::Token;
+
+    fn evaluates_to(condition: &str, expected: bool) {
+        let src = format!("#if {condition}\nyes\n#else\nno\n#endif");
+        let expected = if expected { "yes" } else { "no" };
+        assert_eq!(
+            preprocess_kinds(&src),
+            vec![Token::Identifier(expected.to_string())],
+            "#if {condition}"
+        );
+    }
+
+    #[test]
+    fn arithmetic_and_precedence() {
+        evaluates_to("1 + 2 * 3 == 7", true);
+        evaluates_to("(1 + 2) * 3 == 7", false);
+        evaluates_to("-1 < 0 && 10 % 4 == 2 && (1 << 4) == 16", true);
+        evaluates_to("~0 == -1 && !0 && 6 ^ 3 == 5 - 0", true);
+        evaluates_to("1 ? 2 : 0", true);
+        evaluates_to("0 ? 2 : 0", false);
+        evaluates_to("'a' == 97", true);
+    }
+
+    #[test]
+    fn unsigned_arithmetic() {
+        evaluates_to("-1 < 0u", false);
+        evaluates_to("0xffffffffffffffff > 0", true);
+        evaluates_to("__STDC_VERSION__ >= 201112L", true);
+    }
+
+    #[test]
+    fn defined_and_unknown_identifiers() {
+        evaluates_to("defined __STDC__ && defined(__FILE__)", true);
+        evaluates_to("defined(NOT_A_MACRO) || NOT_A_MACRO", false);
+        evaluates_to("!defined NOT_A_MACRO", true);
+    }
+
+    #[test]
+    fn short_circuit_skips_division_by_zero() {
+        evaluates_to("0 && 1 / 0", false);
+        evaluates_to("1 || 1 % 0", true);
+        evaluates_to("1 ? 1 : 1 / 0", true);
+    }
+
+    #[test]
+    fn division_by_zero() {
+        assert_eq!(
+            preprocess_errors("#if 1 / 0\n#endif"),
+            vec![(
+                String::from("division by zero in #if expression"),
+                String::from("1:7")
+            )]
+        );
+    }
+
+    #[test]
+    fn malformed_numbers() {
+        assert_eq!(
+            preprocess_errors("#if 08\n#endif\n#if 0\n#if 1.2.3\n#endif\n#endif"),
+            vec![(
+                String::from("invalid digit '8' in octal constant"),
+                String::from("1:5")
+            )]
+        );
+    }
+
+    #[test]
+    fn trailing_tokens() {
+        assert_eq!(
+            preprocess_errors("#if 1 2\n#endif"),
+            vec![(
+                String::from("unexpected \"2\" in #if expression"),
+                String::from("1:7")
+            )]
+        );
+    }
+
+    #[test]
+    fn missing_punctuators_are_spelled_out() {
+        assert_eq!(
+            preprocess_errors("#if (1\n#endif\n#if 1 ? 2\n#endif\n#if 1 ? 2 3\n#endif"),
+            vec![
+                (
+                    String::from("expected ')' in #if expression before end of line"),
+                    String::from("1:1")
+                ),
+                (
+                    String::from("expected ':' in #if expression before end of line"),
+                    String::from("3:1")
+                ),
+                (
+                    String::from("expected ':' in #if expression before '3'"),
+                    String::from("5:11")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_condition() {
+        assert_eq!(
+            preprocess_errors("#if\n#endif"),
+            vec![(String::from("#if with no expression"), String::from("1:1"))]
+        );
+    }
+}
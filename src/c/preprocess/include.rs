@@ -0,0 +1,200 @@
+use super::{LineMap, PpToken, Preprocessor, identifier_name, split_lines, tokens_from_source};
+use crate::c::lexer::Token;
+use crate::core::lexer::LexError;
+use crate::core::span::Span;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// the same limit gcc uses, catches files that include themselves
+const MAX_INCLUDE_DEPTH: usize = 200;
+
+/**
+ * Returns the name of the macro guarding a file, if the whole file is wrapped
+ * in `#ifndef NAME` ... `#endif`. Such a file can be skipped without reading it
+ * again as long as NAME stays defined. An `#else` or `#elif` of the outer
+ * group has text that is used when NAME is defined, so it is not a guard
+ */
+fn include_guard(tokens: &[PpToken]) -> Option<String> {
+    let lines = split_lines(tokens.to_vec());
+    let directive_name = |line: &[PpToken]| -> Option<String> {
+        match line {
+            [hash, name, ..] if hash.token == Token::Hash => {
+                identifier_name(&name.token).map(str::to_string)
+            }
+            _ => None,
+        }
+    };
+    let first = lines.first()?;
+    if directive_name(first).as_deref() != Some("ifndef") {
+        return None;
+    }
+    let guard = identifier_name(&first.get(2)?.token)?.to_string();
+    let mut depth = 0;
+    for (index, line) in lines.iter().enumerate() {
+        match directive_name(line).as_deref() {
+            Some("if" | "ifdef" | "ifndef") => depth += 1,
+            Some("else" | "elif") if depth == 1 => return None,
+            Some("endif") => {
+                depth -= 1;
+                if depth == 0 {
+                    return (index == lines.len() - 1).then_some(guard);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+impl Preprocessor<'_> {
+    /**
+     * Finds the file named by an include directive. Quoted names are looked
+     * up next to the including file first, then in the -I directories. Angled
+     * names only use the -I directories
+     */
+    fn resolve_include(&self, name: &str, angled: bool, span: Span) -> Result<PathBuf, LexError> {
+        let current_dir = self
+            .file_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let quoted_dirs = if angled { None } else { Some(current_dir) };
+        quoted_dirs
+            .iter()
+            .chain(self.include_paths.iter())
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())
+            .ok_or_else(|| LexError::new(format!("{name}: No such file or directory"), span))
+    }
+
+    /**
+     * Reads the header name of an include directive. If the line is not
+     * `"name"` or `<name>` it is macro expanded and read again
+     */
+    fn include_target(
+        &mut self,
+        tokens: &[PpToken],
+        directive_span: Span,
+    ) -> Result<(String, bool), LexError> {
+        match tokens {
+            [token] if let Token::HeaderName(name) = &token.token => Ok((name.clone(), true)),
+            [token] if matches!(token.token, Token::StringLiteral(_)) => Ok((
+                token.spelling[1..token.spelling.len() - 1].to_string(),
+                false,
+            )),
+            _ => {
+                let expanded = self.expand(tokens.to_vec());
+                match expanded.as_slice() {
+                    [token] if matches!(token.token, Token::StringLiteral(_)) => Ok((
+                        token.spelling[1..token.spelling.len() - 1].to_string(),
+                        false,
+                    )),
+                    [open, name @ .., close]
+                        if open.token == Token::OpenAngleBracket
+                            && close.token == Token::CloseAngleBracket =>
+                    {
+                        let name =
+                            name.iter()
+                                .enumerate()
+                                .fold(String::new(), |mut name, (i, token)| {
+                                    if i > 0 && token.leading_space {
+                                        name.push(' ');
+                                    }
+                                    name.push_str(&token.spelling);
+                                    name
+                                });
+                        Ok((name, true))
+                    }
+                    _ => Err(LexError::new(
+                        "#include expects \"FILENAME\" or <FILENAME>",
+                        directive_span,
+                    )),
+                }
+            }
+        }
+    }
+
+    /**
+     * Preprocesses an included file and returns its tokens. Tokens keep the
+     * file they came from in their spans. A file that cannot be included is
+     * reported and contributes no tokens
+     */
+    pub(super) fn include(&mut self, tokens: &[PpToken], directive_span: Span) -> Vec<PpToken> {
+        let target = self
+            .include_target(tokens, directive_span)
+            .and_then(|(name, angled)| self.resolve_include(&name, angled, directive_span));
+        let path = match target {
+            Ok(path) => path,
+            Err(error) => {
+                self.errors.push(error);
+                return vec![];
+            }
+        };
+        let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        if self.pragma_once.contains(&canonical)
+            || self
+                .include_guards
+                .get(&canonical)
+                .is_some_and(|guard| self.macros.contains_key(guard))
+        {
+            return vec![];
+        }
+        if self.include_depth >= MAX_INCLUDE_DEPTH {
+            self.error(
+                format!("#include nested depth {MAX_INCLUDE_DEPTH} exceeds maximum"),
+                directive_span,
+            );
+            return vec![];
+        }
+        let Ok(src) = fs::read_to_string(&path) else {
+            self.error(format!("failed to read {}", path.display()), directive_span);
+            return vec![];
+        };
+
+        let file = self.source_map.add(&path.display().to_string());
+        let included: Vec<PpToken> = match tokens_from_source(&src) {
+            Ok(tokens) => tokens,
+            Err(errors) => {
+                self.errors.extend(errors.into_iter().map(|mut error| {
+                    error.span.file = file;
+                    error
+                }));
+                return vec![];
+            }
+        };
+        if let Some(guard) = include_guard(&included) {
+            self.include_guards.insert(canonical.clone(), guard);
+        }
+
+        let outer_path = std::mem::replace(&mut self.file_path, canonical);
+        let outer_line_map = std::mem::replace(&mut self.line_map, LineMap::new(file));
+        self.include_depth += 1;
+        let output = self.run(included);
+        self.include_depth -= 1;
+        self.line_map = outer_line_map;
+        self.file_path = outer_path;
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_include_guards() {
+        let guarded = "#ifndef FOO_H\n#define FOO_H\n#if 1\n#endif\nint x;\n#endif\n";
+        assert_eq!(
+            include_guard(&tokens_from_source(guarded).unwrap()),
+            Some(String::from("FOO_H"))
+        );
+        let trailing = "#ifndef FOO_H\n#define FOO_H\n#endif\nint x;\n";
+        assert_eq!(include_guard(&tokens_from_source(trailing).unwrap()), None);
+        let ifdef = "#ifdef FOO_H\n#endif\n";
+        assert_eq!(include_guard(&tokens_from_source(ifdef).unwrap()), None);
+        let with_else = "#ifndef FOO_H\n#define FOO_H\n#else\nint y;\n#endif\n";
+        assert_eq!(include_guard(&tokens_from_source(with_else).unwrap()), None);
+        let with_elif = "#ifndef FOO_H\n#define FOO_H\n#elif 1\nint y;\n#endif\n";
+        assert_eq!(include_guard(&tokens_from_source(with_elif).unwrap()), None);
+    }
+}
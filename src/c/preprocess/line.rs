@@ -0,0 +1,199 @@
+use super::{PpToken, Preprocessor};
+use crate::c::lexer::Token;
+use crate::core::span::{FileId, Span};
+
+/**
+ * Where the lines of the file being preprocessed really come from. Line
+ * markers (`# 12 "foo.h" 2`, as written by `gcc -E`) and `#line` change it
+ * for every line that follows them
+ */
+#[derive(Debug, Clone, Copy)]
+pub(super) struct LineMap {
+    file: FileId,
+    line_delta: isize,
+}
+
+impl LineMap {
+    pub(super) fn new(file: FileId) -> Self {
+        LineMap {
+            file,
+            line_delta: 0,
+        }
+    }
+
+    /**
+     * Moves the tokens of one logical line to their presumed file and line
+     */
+    pub(super) fn apply(&self, tokens: &mut [PpToken]) {
+        for token in tokens.iter_mut() {
+            token.span.file = self.file;
+            token.span.line = token.span.line.saturating_add_signed(self.line_delta);
+        }
+    }
+}
+
+impl Preprocessor<'_> {
+    /**
+     * Handles `#line N "file"` and the `# N "file" flags` line markers. Both
+     * say that the next line is line N of "file". The flags of a line marker
+     * only matter for system headers and are ignored. `#line` operands are
+     * macro expanded if they are not already a line number. A malformed
+     * directive is reported and leaves the line map unchanged
+     */
+    pub(super) fn line_directive(
+        &mut self,
+        tokens: &[PpToken],
+        directive_span: Span,
+        marker: bool,
+    ) {
+        let tokens = match tokens.first().map(|token| &token.token) {
+            Some(Token::PpNumber(_)) => tokens.to_vec(),
+            _ if marker => tokens.to_vec(),
+            _ => self.expand(tokens.to_vec()),
+        };
+        let directive = if marker { "line marker" } else { "#line" };
+        let line = match tokens.first() {
+            Some(token)
+                if matches!(token.token, Token::PpNumber(_))
+                    && token.spelling.bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                token.spelling.parse::<usize>().ok()
+            }
+            _ => None,
+        };
+        let Some(line) = line.filter(|line| marker || (1..=2147483647).contains(line)) else {
+            self.error(format!("{directive} expects a line number"), directive_span);
+            return;
+        };
+        let flags = tokens.get(2..).unwrap_or_default();
+        if flags
+            .iter()
+            .any(|token| !marker || !matches!(token.token, Token::PpNumber(_)))
+        {
+            self.error(format!("extra tokens after {directive}"), directive_span);
+            return;
+        }
+        let file = match tokens.get(1) {
+            None => self.line_map.file,
+            Some(PpToken {
+                token: Token::StringLiteral(name),
+                ..
+            }) => self.source_map.add(&String::from_utf8_lossy(name)),
+            Some(token) => {
+                self.error(
+                    format!("invalid filename \"{}\" in {directive}", token.spelling),
+                    token.span,
+                );
+                return;
+            }
+        };
+        // the original line of the directive, before any earlier mapping
+        let physical_line = directive_span
+            .line
+            .saturating_add_signed(-self.line_map.line_delta);
+        self.line_map = LineMap {
+            file,
+            line_delta: line as isize - (physical_line as isize + 1),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::preprocess;
+    use super::super::tests::{preprocess_errors, preprocess_kinds, preprocess_str};
+    use crate::c::lexer::{IntegerType, Token};
+    use crate::core::diagnostic::Diagnostic;
+    use crate::core::source_map::SourceMap;
+    use crate::core::span::FileId;
+
+    fn locations(src: &str) -> Vec<(FileId, usize, usize)> {
+        preprocess_str(src)
+            .into_iter()
+            .map(|token| (token.span.file, token.span.line, token.span.column))
+            .collect()
+    }
+
+    #[test]
+    fn line_markers_move_following_lines() {
+        assert_eq!(
+            locations("a\n# 10 \"foo.h\" 1 3\nb\n\nc\n# 2 \"test.c\" 2\nd"),
+            vec![
+                (FileId(0), 1, 1),
+                (FileId(1), 10, 1),
+                (FileId(1), 12, 1),
+                (FileId(0), 2, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn line_directive_keeps_file_unless_given() {
+        assert_eq!(
+            locations("#define N 40\n#line N\na\n#line 7 \"other.c\"\nb"),
+            vec![(FileId(0), 40, 1), (FileId(1), 7, 1)]
+        );
+    }
+
+    #[test]
+    fn predefined_macros_follow_line_markers() {
+        assert_eq!(
+            preprocess_kinds("# 20 \"foo.h\"\n__LINE__ __FILE__"),
+            vec![
+                Token::Constant(20, IntegerType::Int),
+                Token::StringLiteral(b"foo.h".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_line_directives() {
+        assert_eq!(
+            preprocess_errors("#line foo\n#line 0\n#line 3 foo\n#line 3 \"a.c\" 1\n# 3 x"),
+            vec![
+                (
+                    String::from("#line expects a line number"),
+                    String::from("1:1")
+                ),
+                (
+                    String::from("#line expects a line number"),
+                    String::from("2:1")
+                ),
+                (
+                    String::from("invalid filename \"foo\" in #line"),
+                    String::from("3:9")
+                ),
+                (
+                    String::from("extra tokens after #line"),
+                    String::from("4:1")
+                ),
+                (
+                    String::from("invalid filename \"x\" in line marker"),
+                    String::from("5:5")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_after_line_directive_render_presumed_file() {
+        let mut source_map = SourceMap::new("test.c");
+        let errors = preprocess(
+            "#line 10 \"foo.h\"\n#line bar",
+            "test.c",
+            &[],
+            &mut source_map,
+        )
+        .unwrap_err();
+        let rendered: Vec<String> = errors
+            .into_iter()
+            .map(|error| Diagnostic::from(error).render(&source_map))
+            .collect();
+        assert_eq!(
+            rendered,
+            vec![String::from(
+                "foo.h:10:1: error: #line expects a line number"
+            )]
+        );
+    }
+}
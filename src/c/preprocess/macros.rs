@@ -0,0 +1,725 @@
+use super::{PpToken, Preprocessor, identifier_name};
+use crate::c::lexer::{self, Token};
+use crate::core::lexer::LexError;
+use crate::core::span::Span;
+use std::collections::{HashSet, VecDeque};
+
+#[derive(Debug, Clone)]
+pub(super) enum Macro {
+    Object(Vec<PpToken>),
+    Function {
+        params: Vec<String>,
+        variadic: bool,
+        body: Vec<PpToken>,
+    },
+    // predefined macros whose value depends on where they are used
+    Line,
+    File,
+}
+
+const VA_ARGS: &str = "__VA_ARGS__";
+
+/**
+ * Escapes `text` so it can be placed inside a string literal
+ */
+fn escape_for_string_literal(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/**
+ * Lexes a piece of text that must form exactly one token, as produced by `#`
+ * and `##`
+ */
+fn single_token(spelling: String, span: Span) -> Option<PpToken> {
+    let mut tokens = lexer::lex_lines(&spelling).ok()?;
+    if tokens.len() != 1 || matches!(tokens[0].node, Token::Invalid(..) | Token::Newline) {
+        return None;
+    }
+    Some(PpToken::new(tokens.remove(0).node, span, spelling))
+}
+
+/**
+ * `#param`: turns the tokens of an argument into a string literal, keeping a
+ * single space wherever the argument had whitespace
+ */
+fn stringize(argument: &[PpToken], span: Span) -> PpToken {
+    let mut text = String::new();
+    for (i, token) in argument.iter().enumerate() {
+        if i > 0 && token.leading_space {
+            text.push(' ');
+        }
+        match token.token {
+            Token::StringLiteral(_) | Token::CharLiteral(_) => {
+                text.push_str(&escape_for_string_literal(&token.spelling))
+            }
+            _ => text.push_str(&token.spelling),
+        }
+    }
+    single_token(format!("\"{text}\""), span).expect("stringized text is a string literal")
+}
+
+/**
+ * `lhs ## rhs`: the spellings are joined and must form a single token
+ */
+fn paste(lhs: &PpToken, rhs: &PpToken) -> Result<PpToken, LexError> {
+    let Some(mut token) = single_token(format!("{}{}", lhs.spelling, rhs.spelling), lhs.span)
+    else {
+        return Err(LexError::new(
+            format!(
+                "pasting \"{}\" and \"{}\" does not give a valid preprocessing token",
+                lhs.spelling, rhs.spelling
+            ),
+            rhs.span,
+        ));
+    };
+    token.leading_space = lhs.leading_space;
+    token.hideset = lhs.hideset.union(&rhs.hideset).cloned().collect();
+    Ok(token)
+}
+
+fn check_paste_operands(body: &[PpToken], name: &str) -> Result<(), LexError> {
+    let misplaced = [body.first(), body.last()]
+        .into_iter()
+        .flatten()
+        .find(|token| token.token == Token::DoubleHash);
+    match misplaced {
+        Some(token) => Err(LexError::new(
+            format!("'##' cannot appear at either end of macro {name}"),
+            token.span,
+        )),
+        None => Ok(()),
+    }
+}
+
+/**
+ * Reads the parameter list of a function-like macro, starting after its
+ * opening parenthesis. Returns the parameters, whether the macro is variadic
+ * and the index of the closing parenthesis
+ */
+fn parse_parameters(
+    tokens: &[PpToken],
+    name: &str,
+    directive_span: Span,
+) -> Result<(Vec<String>, bool, usize), LexError> {
+    let mut params: Vec<String> = vec![];
+    let mut variadic = false;
+    let missing_parenthesis = || {
+        LexError::new(
+            format!("missing ')' in parameters of macro {name}"),
+            directive_span,
+        )
+    };
+    let mut i = 2;
+    if tokens.get(i).map(|token| &token.token) == Some(&Token::CloseParenthesis) {
+        return Ok((params, variadic, i));
+    }
+    loop {
+        let token = tokens.get(i).ok_or_else(missing_parenthesis)?;
+        match (&token.token, identifier_name(&token.token)) {
+            (Token::Ellipsis, _) => variadic = true,
+            (_, Some(param)) if param != VA_ARGS => {
+                if params.iter().any(|p| p == param) {
+                    return Err(LexError::new(
+                        format!("duplicate macro parameter {param}"),
+                        token.span,
+                    ));
+                }
+                params.push(param.to_string());
+            }
+            _ => {
+                return Err(LexError::new(
+                    format!(
+                        "expected parameter name in macro {name} but found {}",
+                        token.spelling
+                    ),
+                    token.span,
+                ));
+            }
+        }
+        i += 1;
+        let token = tokens.get(i).ok_or_else(missing_parenthesis)?;
+        match token.token {
+            Token::CloseParenthesis => return Ok((params, variadic, i)),
+            // nothing may follow the ellipsis
+            Token::Comma if !variadic => i += 1,
+            _ => {
+                return Err(LexError::new(
+                    format!(
+                        "expected ',' or ')' in parameters of macro {name} but found {}",
+                        token.spelling
+                    ),
+                    token.span,
+                ));
+            }
+        }
+    }
+}
+
+/**
+ * Reads the name and definition of a macro from a `#define` line
+ */
+fn definition(tokens: &[PpToken], directive_span: Span) -> Result<(String, Macro), LexError> {
+    let Some(name) = tokens
+        .first()
+        .and_then(|token| identifier_name(&token.token))
+    else {
+        return Err(LexError::new(
+            "macro names must be identifiers",
+            directive_span,
+        ));
+    };
+    if name == "defined" {
+        return Err(LexError::new(
+            "\"defined\" cannot be used as a macro name",
+            directive_span,
+        ));
+    }
+    let is_function_like = tokens
+        .get(1)
+        .is_some_and(|token| token.token == Token::OpenParenthesis && !token.leading_space);
+    if !is_function_like {
+        let body = tokens[1..].to_vec();
+        check_paste_operands(&body, name)?;
+        return Ok((name.to_string(), Macro::Object(body)));
+    }
+
+    let (mut params, variadic, close) = parse_parameters(tokens, name, directive_span)?;
+    let body = tokens[close + 1..].to_vec();
+    check_paste_operands(&body, name)?;
+    for (j, token) in body.iter().enumerate() {
+        let next_is_param = body
+            .get(j + 1)
+            .and_then(|next| identifier_name(&next.token))
+            .is_some_and(|next| params.iter().any(|p| p == next) || (variadic && next == VA_ARGS));
+        if token.token == Token::Hash && !next_is_param {
+            return Err(LexError::new(
+                "'#' is not followed by a macro parameter",
+                token.span,
+            ));
+        }
+    }
+    if variadic {
+        params.push(VA_ARGS.to_string());
+    }
+    Ok((
+        name.to_string(),
+        Macro::Function {
+            params,
+            variadic,
+            body,
+        },
+    ))
+}
+
+impl Preprocessor<'_> {
+    /**
+     * `#define NAME body` or `#define NAME(params) body`. A macro is
+     * function-like only if the parenthesis directly follows the name. An
+     * invalid definition is reported and leaves the macro undefined
+     */
+    pub(super) fn define(&mut self, tokens: &[PpToken], directive_span: Span) {
+        match definition(tokens, directive_span) {
+            Ok((name, definition)) => {
+                self.macros.insert(name, definition);
+            }
+            Err(error) => self.errors.push(error),
+        }
+    }
+
+    pub(super) fn undef(&mut self, tokens: &[PpToken], directive_span: Span) {
+        match tokens
+            .first()
+            .and_then(|token| identifier_name(&token.token))
+        {
+            Some(name) => {
+                self.macros.remove(name);
+            }
+            None => self.error("macro names must be identifiers", directive_span),
+        }
+    }
+
+    /**
+     * Fully macro-expands a token sequence. Expansions are pushed back onto
+     * the input so they are rescanned, hide sets stop a macro from expanding
+     * inside its own expansion
+     */
+    pub(super) fn expand(&mut self, tokens: Vec<PpToken>) -> Vec<PpToken> {
+        let mut input: VecDeque<PpToken> = tokens.into();
+        let mut output: Vec<PpToken> = vec![];
+        while let Some(token) = input.pop_front() {
+            let Some(name) = identifier_name(&token.token) else {
+                output.push(token);
+                continue;
+            };
+            let name = name.to_string();
+            if token.hideset.contains(&name) {
+                output.push(token);
+                continue;
+            }
+            // cloned so that expanding the body can report errors
+            let expansion = match self.macros.get(&name).cloned() {
+                None => None,
+                Some(Macro::Line) => Some(vec![PpToken::number(token.span.line, token.span)]),
+                Some(Macro::File) => Some(vec![
+                    single_token(
+                        format!(
+                            "\"{}\"",
+                            escape_for_string_literal(self.source_map.name(token.span.file))
+                        ),
+                        token.span,
+                    )
+                    .expect("file name is a string literal"),
+                ]),
+                Some(Macro::Object(body)) => {
+                    let mut hideset = token.hideset.clone();
+                    hideset.insert(name.clone());
+                    let tokens = self.substitute(&body, &[], &[], false);
+                    Some(finish_expansion(tokens, &token, &hideset))
+                }
+                Some(Macro::Function {
+                    params,
+                    variadic,
+                    body,
+                }) => {
+                    if input.front().map(|t| &t.token) != Some(&Token::OpenParenthesis) {
+                        None
+                    } else {
+                        match collect_arguments(&mut input, &name, params.len(), variadic, &token) {
+                            Ok((args, close)) => {
+                                let mut hideset: HashSet<String> = token
+                                    .hideset
+                                    .intersection(&close.hideset)
+                                    .cloned()
+                                    .collect();
+                                hideset.insert(name.clone());
+                                let tokens = self.substitute(&body, &params, &args, true);
+                                Some(finish_expansion(tokens, &token, &hideset))
+                            }
+                            // the invocation is dropped
+                            Err(error) => {
+                                self.errors.push(error);
+                                Some(vec![])
+                            }
+                        }
+                    }
+                }
+            };
+            match expansion {
+                Some(tokens) => {
+                    for expanded in tokens.into_iter().rev() {
+                        input.push_front(expanded);
+                    }
+                }
+                None => output.push(token),
+            }
+        }
+        output
+    }
+
+    /**
+     * Replaces parameters in a macro body with their arguments and applies the
+     * `#` and `##` operators. Arguments are macro-expanded first unless they
+     * are operands of `#` or `##`
+     */
+    fn substitute(
+        &mut self,
+        body: &[PpToken],
+        params: &[String],
+        args: &[Vec<PpToken>],
+        function_like: bool,
+    ) -> Vec<PpToken> {
+        let argument = |token: &PpToken| {
+            identifier_name(&token.token)
+                .and_then(|name| params.iter().position(|p| p == name))
+                .map(|index| &args[index])
+        };
+        let mut output: Vec<PpToken> = vec![];
+        // set when the left operand of a pending `##` was an empty argument
+        let mut placemarker = false;
+        let mut i = 0;
+        while i < body.len() {
+            let token = &body[i];
+            let next = body.get(i + 1);
+            let followed_by_paste = body
+                .get(i + 1)
+                .is_some_and(|next| next.token == Token::DoubleHash);
+
+            if function_like && token.token == Token::Hash {
+                let arg = argument(next.expect("'#' is followed by a parameter"))
+                    .expect("'#' is followed by a parameter");
+                output.push(stringize(arg, token.span));
+                placemarker = false;
+                i += 2;
+                continue;
+            }
+
+            if token.token == Token::DoubleHash {
+                let rhs = next.expect("'##' is not at the end of a macro");
+                let rhs_tokens: Vec<PpToken> = match argument(rhs) {
+                    Some(arg) => arg.clone(),
+                    None => vec![rhs.clone()],
+                };
+                // pasting two placemarkers gives a placemarker, which the next
+                // `##` must not look past
+                let pasted_placemarker = placemarker && rhs_tokens.is_empty();
+                // GNU extension, `, ## __VA_ARGS__` drops the comma when no
+                // variable arguments are given and doesn't paste otherwise
+                let is_comma_va_args = identifier_name(&rhs.token) == Some(VA_ARGS)
+                    && output.last().map(|t| &t.token) == Some(&Token::Comma);
+                if is_comma_va_args && rhs_tokens.is_empty() {
+                    output.pop();
+                } else if placemarker || is_comma_va_args {
+                    output.extend(rhs_tokens);
+                } else if !rhs_tokens.is_empty() {
+                    let lhs = output.pop().expect("'##' is not at the start of a macro");
+                    // an invalid paste leaves both operands as they are
+                    match paste(&lhs, &rhs_tokens[0]) {
+                        Ok(pasted) => output.push(pasted),
+                        Err(error) => {
+                            self.errors.push(error);
+                            output.extend([lhs, rhs_tokens[0].clone()]);
+                        }
+                    }
+                    output.extend(rhs_tokens[1..].iter().cloned());
+                }
+                placemarker = pasted_placemarker;
+                i += 2;
+                continue;
+            }
+
+            match argument(token) {
+                Some(arg) if followed_by_paste => {
+                    placemarker = arg.is_empty();
+                    output.extend(arg.iter().cloned());
+                }
+                Some(arg) => {
+                    let mut expanded = self.expand(arg.clone());
+                    if let Some(first) = expanded.first_mut() {
+                        first.leading_space = token.leading_space;
+                    }
+                    output.extend(expanded);
+                    placemarker = false;
+                }
+                None => {
+                    output.push(token.clone());
+                    placemarker = false;
+                }
+            }
+            i += 1;
+        }
+        output
+    }
+}
+
+/**
+ * Gives the tokens of an expansion the location of the macro invocation and
+ * adds the macro's hide set to them
+ */
+fn finish_expansion(
+    mut tokens: Vec<PpToken>,
+    invocation: &PpToken,
+    hideset: &HashSet<String>,
+) -> Vec<PpToken> {
+    for token in tokens.iter_mut() {
+        token.span = invocation.span;
+        token.hideset.extend(hideset.iter().cloned());
+    }
+    if let Some(first) = tokens.first_mut() {
+        first.leading_space = invocation.leading_space;
+    }
+    tokens
+}
+
+/**
+ * Reads the parenthesized arguments of a function-like macro invocation from
+ * the front of `input`. Returns the arguments and the closing parenthesis.
+ * For variadic macros everything after the named parameters, commas
+ * included, becomes the last argument
+ */
+fn collect_arguments(
+    input: &mut VecDeque<PpToken>,
+    name: &str,
+    param_count: usize,
+    variadic: bool,
+    invocation: &PpToken,
+) -> Result<(Vec<Vec<PpToken>>, PpToken), LexError> {
+    input.pop_front(); // (
+    let named_count = if variadic {
+        param_count - 1
+    } else {
+        param_count
+    };
+    let mut args: Vec<Vec<PpToken>> = vec![vec![]];
+    let mut depth = 0;
+    let close = loop {
+        let Some(token) = input.pop_front() else {
+            return Err(LexError::new(
+                format!("unterminated argument list invoking macro {name}"),
+                invocation.span,
+            ));
+        };
+        match token.token {
+            Token::OpenParenthesis => depth += 1,
+            Token::CloseParenthesis if depth == 0 => break token,
+            Token::CloseParenthesis => depth -= 1,
+            Token::Comma if depth == 0 && (!variadic || args.len() <= named_count) => {
+                args.push(vec![]);
+                continue;
+            }
+            _ => {}
+        }
+        args.last_mut().unwrap().push(token);
+    };
+
+    // `F()` passes a single empty argument, which is no arguments at all for
+    // a macro without parameters
+    if param_count == 0 && args.len() == 1 && args[0].is_empty() {
+        args.clear();
+    }
+    // the variable arguments may be left out entirely
+    if variadic && args.len() == named_count {
+        args.push(vec![]);
+    }
+    if args.len() != param_count {
+        return Err(LexError::new(
+            format!(
+                "macro {name} passed {} arguments, but takes {}",
+                args.len(),
+                named_count
+            ),
+            invocation.span,
+        ));
+    }
+    Ok((args, close))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{preprocess_errors, preprocess_str};
+    use crate::c::lexer::{self, Token};
+
+    fn assert_preprocesses_to(src: &str, expected: &str) {
+        let kinds = |tokens: Vec<crate::core::span::Spanned<Token>>| {
+            tokens
+                .into_iter()
+                .map(|token| token.node)
+                .collect::<Vec<Token>>()
+        };
+        assert_eq!(
+            kinds(preprocess_str(src)),
+            kinds(lexer::lex_contents(expected.to_string()).unwrap())
+        );
+    }
+
+    #[test]
+    fn object_like_macros() {
+        assert_preprocesses_to(
+            "#define TWO 2\n#define FOUR TWO + TWO\nreturn FOUR * TWO;",
+            "return 2 + 2 * 2;",
+        );
+    }
+
+    #[test]
+    fn undef_removes_macro() {
+        assert_preprocesses_to("#define X 1\nX\n#undef X\nX", "1 X");
+    }
+
+    #[test]
+    fn keywords_can_be_macro_names() {
+        assert_preprocesses_to("#define int void\nint main", "void main");
+    }
+
+    #[test]
+    fn function_like_macros() {
+        assert_preprocesses_to(
+            "#define ADD(a, b) ((a) + (b))\nADD(1, ADD(2, 3))",
+            "((1) + (((2) + (3))))",
+        );
+    }
+
+    #[test]
+    fn function_like_macro_without_parentheses_is_not_expanded() {
+        assert_preprocesses_to("#define F(x) x\nint F;", "int F;");
+    }
+
+    #[test]
+    fn space_before_parenthesis_makes_object_like_macro() {
+        assert_preprocesses_to("#define F (x) x\nF(1)", "(x) x(1)");
+    }
+
+    #[test]
+    fn invocation_spans_lines_and_nests_parentheses() {
+        assert_preprocesses_to("#define FIRST(a, b) a\nFIRST((1, 2),\n  3)", "(1, 2)");
+    }
+
+    #[test]
+    fn empty_argument_lists() {
+        assert_preprocesses_to("#define NONE() 1\n#define ONE(x) (x)\nNONE() ONE()", "1 ()");
+    }
+
+    #[test]
+    fn self_reference_is_not_expanded_again() {
+        assert_preprocesses_to(
+            "#define foo foo + 1\n#define a b\n#define b a\nfoo a b",
+            "foo + 1 a b",
+        );
+    }
+
+    #[test]
+    fn stringification() {
+        assert_preprocesses_to(
+            "#define STR(x) #x\nSTR(a  +   b) STR( \"q\\n\" ) STR()",
+            "\"a + b\" \"\\\"q\\\\n\\\"\" \"\"",
+        );
+    }
+
+    #[test]
+    fn token_pasting() {
+        assert_preprocesses_to(
+            "#define CAT(a, b) a ## b\n#define VAR(n) var ## n ## _x\nCAT(x, 1) CAT(, y) CAT(z,) VAR(2) CAT(<, <)",
+            "x1 y z var2_x <<",
+        );
+    }
+
+    #[test]
+    fn pasting_placemarkers() {
+        // the example of C17 6.10.3.3
+        assert_preprocesses_to(
+            "#define t(x,y,z) x ## y ## z\nint j[] = { t(1,2,3), t(,4,5), t(6,,7), t(8,9,),\n  t(10,,), t(,11,), t(,,12), t(,,) };",
+            "int j[] = { 123, 45, 67, 89, 10, 11, 12, };",
+        );
+        assert_preprocesses_to(
+            "#define t(x,y,z) [x ## y ## z]\nt(,,12) t(,,) t(1,,)",
+            "[12] [] [1]",
+        );
+    }
+
+    #[test]
+    fn numbers_keep_their_spelling_until_expanded() {
+        assert_preprocesses_to(
+            "#define f(x) #x\n#define CAT(a, b) a ## b\nf(1.2.3) f(0x) CAT(0x, 1F) CAT(1, 0u)",
+            "\"1.2.3\" \"0x\" 0x1F 10u",
+        );
+        assert_eq!(
+            preprocess_errors("#define CAT(a, b) a ## b\nCAT(0x, )"),
+            vec![(
+                String::from("hexadecimal constant 0x has no digits"),
+                String::from("2:1")
+            )]
+        );
+    }
+
+    #[test]
+    fn pasted_arguments_are_not_expanded_first() {
+        assert_preprocesses_to(
+            "#define ONE 1\n#define CAT(a, b) a ## b\n#define XCAT(a, b) CAT(a, b)\nCAT(ONE, 2) XCAT(ONE, 2)",
+            "ONE2 12",
+        );
+    }
+
+    #[test]
+    fn variadic_macros() {
+        assert_preprocesses_to(
+            "#define F(fmt, ...) f(fmt, __VA_ARGS__)\n#define G(...) g(__VA_ARGS__)\n#define H(fmt, ...) h(fmt, ## __VA_ARGS__)\nF(1, 2, 3) G() G(a, (b, c)) H(1) H(1, 2)",
+            "f(1, 2, 3) g() g(a, (b, c)) h(1) h(1, 2)",
+        );
+    }
+
+    #[test]
+    fn predefined_macros() {
+        assert_preprocesses_to(
+            "#define LINE __LINE__\n__STDC__ __STDC_VERSION__\n__FILE__\nLINE",
+            "1 201710L \"test.c\" 4",
+        );
+    }
+
+    #[test]
+    fn wrong_argument_count() {
+        assert_eq!(
+            preprocess_errors("#define ADD(a, b) a + b\nADD(1)"),
+            vec![(
+                String::from("macro ADD passed 1 arguments, but takes 2"),
+                String::from("2:1")
+            )]
+        );
+    }
+
+    #[test]
+    fn unterminated_invocation() {
+        assert_eq!(
+            preprocess_errors("#define F(a) a\nF(1, 2"),
+            vec![(
+                String::from("unterminated argument list invoking macro F"),
+                String::from("2:1")
+            )]
+        );
+    }
+
+    #[test]
+    fn stringify_requires_parameter() {
+        assert_eq!(
+            preprocess_errors("#define F(a) #b"),
+            vec![(
+                String::from("'#' is not followed by a macro parameter"),
+                String::from("1:14")
+            )]
+        );
+    }
+
+    #[test]
+    fn paste_at_end_of_macro() {
+        assert_eq!(
+            preprocess_errors("#define F ## x"),
+            vec![(
+                String::from("'##' cannot appear at either end of macro F"),
+                String::from("1:11")
+            )]
+        );
+    }
+
+    #[test]
+    fn invalid_paste() {
+        assert_eq!(
+            preprocess_errors("#define CAT(a, b) a ## b\nCAT(+, -)"),
+            vec![(
+                String::from("pasting \"+\" and \"-\" does not give a valid preprocessing token"),
+                String::from("2:8")
+            )]
+        );
+    }
+
+    #[test]
+    fn invalid_definitions() {
+        assert_eq!(
+            preprocess_errors(
+                "#define F(a, a) a\n#define G(a b\n#define H(a\n#define 1\n#define defined\n#undef"
+            ),
+            vec![
+                (
+                    String::from("duplicate macro parameter a"),
+                    String::from("1:14")
+                ),
+                (
+                    String::from("expected ',' or ')' in parameters of macro G but found b"),
+                    String::from("2:13")
+                ),
+                (
+                    String::from("missing ')' in parameters of macro H"),
+                    String::from("3:1")
+                ),
+                (
+                    String::from("macro names must be identifiers"),
+                    String::from("4:1")
+                ),
+                (
+                    String::from("\"defined\" cannot be used as a macro name"),
+                    String::from("5:1")
+                ),
+                (
+                    String::from("macro names must be identifiers"),
+                    String::from("6:1")
+                ),
+            ]
+        );
+    }
+}
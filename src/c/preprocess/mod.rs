@@ -0,0 +1,442 @@
+mod conditional;
+mod expression;
+mod include;
+mod line;
+mod macros;
+
+use super::lexer::{self, Token};
+use crate::core::lexer::LexError;
+use crate::core::source_map::SourceMap;
+use crate::core::span::{FileId, Span, Spanned};
+use conditional::ConditionalStack;
+use line::LineMap;
+use macros::Macro;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+/**
+ * A token as seen by the preprocessor. Besides the token itself we keep how it
+ * was spelled in the source (needed by `#` and `##`), whether whitespace came
+ * before it, and its hide set: the macros that must not be expanded again from
+ * this token (Prosser's algorithm)
+ */
+#[derive(Debug, Clone)]
+struct PpToken {
+    token: Token,
+    span: Span,
+    spelling: String,
+    leading_space: bool,
+    hideset: HashSet<String>,
+}
+
+impl PpToken {
+    fn new(token: Token, span: Span, spelling: String) -> Self {
+        PpToken {
+            token,
+            span,
+            spelling,
+            leading_space: false,
+            hideset: HashSet::new(),
+        }
+    }
+
+    /**
+     * A number the preprocessor produces itself, like the value of `__LINE__`
+     */
+    fn number(value: impl ToString, span: Span) -> Self {
+        let spelling = value.to_string();
+        PpToken::new(Token::PpNumber(spelling.clone()), span, spelling)
+    }
+}
+
+/**
+ * Keywords are plain identifiers to the preprocessor, `#define int long` is
+ * allowed
+ */
+fn identifier_name(token: &Token) -> Option<&str> {
+    match token {
+        Token::Identifier(name) => Some(name),
+        Token::Keyword(keyword) => Some(keyword.as_str()),
+        _ => None,
+    }
+}
+
+/**
+ * Lexes `src` into preprocessing tokens, keeping the spelling of each token
+ * as written in the source
+ */
+fn tokens_from_source(src: &str) -> Result<Vec<PpToken>, Vec<LexError>> {
+    let mut tokens: Vec<PpToken> = vec![];
+    let mut previous_end: Option<usize> = None;
+    for Spanned { node, span } in lexer::lex_lines(src)? {
+        let spelling = src[span.offset..span.offset + span.length]
+            .replace("\\\r\n", "")
+            .replace("\\\n", "");
+        let mut token = PpToken::new(node, span, spelling);
+        token.leading_space = previous_end.is_some_and(|end| end != span.offset);
+        previous_end = Some(span.offset + span.length);
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/**
+ * Splits a token stream on `Token::Newline` into logical lines. Empty lines
+ * are dropped
+ */
+fn split_lines(tokens: Vec<PpToken>) -> Vec<Vec<PpToken>> {
+    let mut lines: Vec<Vec<PpToken>> = vec![vec![]];
+    for token in tokens {
+        match token.token {
+            Token::Newline => lines.push(vec![]),
+            _ => lines.last_mut().unwrap().push(token),
+        }
+    }
+    lines.retain(|line| !line.is_empty());
+    lines
+}
+
+struct Preprocessor<'a> {
+    macros: HashMap<String, Macro>,
+    // path of the file currently being preprocessed, and where its lines
+    // claim to come from
+    file_path: PathBuf,
+    line_map: LineMap,
+    include_paths: Vec<PathBuf>,
+    source_map: &'a mut SourceMap,
+    include_depth: usize,
+    // canonical paths of files that used #pragma once
+    pragma_once: HashSet<PathBuf>,
+    // canonical paths of files wrapped in an include guard, and the guard
+    include_guards: HashMap<PathBuf, String>,
+    // malformed directives and macro invocations, and lexical errors in
+    // included files
+    errors: Vec<LexError>,
+}
+
+impl<'a> Preprocessor<'a> {
+    fn new(file_name: &str, include_paths: &[String], source_map: &'a mut SourceMap) -> Self {
+        let mut preprocessor = Preprocessor {
+            macros: HashMap::new(),
+            file_path: PathBuf::from(file_name),
+            line_map: LineMap::new(FileId(0)),
+            include_paths: include_paths.iter().map(PathBuf::from).collect(),
+            source_map,
+            include_depth: 0,
+            pragma_once: HashSet::new(),
+            include_guards: HashMap::new(),
+            errors: vec![],
+        };
+        preprocessor
+            .macros
+            .insert(String::from("__LINE__"), Macro::Line);
+        preprocessor
+            .macros
+            .insert(String::from("__FILE__"), Macro::File);
+        for definition in ["__STDC__ 1", "__STDC_VERSION__ 201710L"] {
+            let tokens = tokens_from_source(definition).expect("predefined macros are valid");
+            preprocessor.define(&tokens, Span::default());
+        }
+        preprocessor
+    }
+
+    /**
+     * Runs directives and expands macros in text lines of one file.
+     * Consecutive text lines are expanded together since a macro invocation
+     * may span several lines. Lines in skipped conditional groups are dropped
+     * and only conditional directives in them are looked at, to track nesting
+     */
+    fn run(&mut self, tokens: Vec<PpToken>) -> Vec<PpToken> {
+        let mut output: Vec<PpToken> = vec![];
+        let mut text: Vec<PpToken> = vec![];
+        let mut conditionals = ConditionalStack::default();
+        for mut line in split_lines(tokens) {
+            self.line_map.apply(&mut line);
+            if line[0].token != Token::Hash {
+                if conditionals.active() {
+                    text.extend(line);
+                }
+                continue;
+            }
+            output.extend(self.expand(std::mem::take(&mut text)));
+            if !self.conditional_directive(&line, &mut conditionals) && conditionals.active() {
+                output.extend(self.directive(&line));
+            }
+        }
+        output.extend(self.expand(text));
+        self.errors.extend(conditionals.finish());
+        output
+    }
+
+    fn error(&mut self, message: impl Into<String>, span: Span) {
+        self.errors.push(LexError::new(message, span));
+    }
+
+    /**
+     * Whether the macro named by an `#ifdef` or `#ifndef` line is defined. A
+     * missing name is reported and counts as not defined
+     */
+    fn is_defined(&mut self, directive: &str, tokens: &[PpToken], directive_span: Span) -> bool {
+        match tokens
+            .first()
+            .and_then(|token| identifier_name(&token.token))
+        {
+            Some(macro_name) => self.macros.contains_key(macro_name),
+            None => {
+                self.error(format!("#{directive} expects a macro name"), directive_span);
+                false
+            }
+        }
+    }
+
+    /**
+     * Handles #if, #ifdef, #ifndef, #elif, #else and #endif. Returns false
+     * for any other line
+     */
+    fn conditional_directive(
+        &mut self,
+        line: &[PpToken],
+        conditionals: &mut ConditionalStack,
+    ) -> bool {
+        let hash_span = line[0].span;
+        let Some(name) = line.get(1).and_then(|token| identifier_name(&token.token)) else {
+            return false;
+        };
+        let rest = &line[2..];
+        let result = match name {
+            "if" => {
+                conditionals.push(hash_span, || self.evaluate_condition(rest, hash_span));
+                Ok(())
+            }
+            "ifdef" => {
+                conditionals.push(hash_span, || self.is_defined(name, rest, hash_span));
+                Ok(())
+            }
+            "ifndef" => {
+                conditionals.push(hash_span, || !self.is_defined(name, rest, hash_span));
+                Ok(())
+            }
+            "elif" => conditionals.elif(hash_span, || self.evaluate_condition(rest, hash_span)),
+            "else" => conditionals.else_branch(hash_span),
+            "endif" => conditionals.endif(hash_span),
+            _ => return false,
+        };
+        if let Err(error) = result {
+            self.errors.push(error);
+        }
+        true
+    }
+
+    /**
+     * Runs any directive other than the conditional ones, returning the
+     * tokens it produces
+     */
+    fn directive(&mut self, line: &[PpToken]) -> Vec<PpToken> {
+        let hash_span = line[0].span;
+        // a lone # is the null directive and does nothing
+        let Some(name_token) = line.get(1) else {
+            return vec![];
+        };
+        let Some(name) = identifier_name(&name_token.token) else {
+            if let Token::PpNumber(_) = name_token.token {
+                self.line_directive(&line[1..], hash_span, true);
+                return vec![];
+            }
+            self.error(
+                format!("invalid preprocessing directive #{}", name_token.spelling),
+                name_token.span,
+            );
+            return vec![];
+        };
+        match name {
+            "define" => self.define(&line[2..], hash_span),
+            "undef" => self.undef(&line[2..], hash_span),
+            "include" => return self.include(&line[2..], hash_span),
+            "error" => {
+                let message = line[2..]
+                    .iter()
+                    .map(|token| token.spelling.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(" ");
+                self.error(format!("#error {message}"), hash_span);
+            }
+            "pragma" => {
+                // #pragma once is the only pragma, unknown pragmas are ignored
+                if line.get(2).and_then(|token| identifier_name(&token.token)) == Some("once") {
+                    let path = fs::canonicalize(&self.file_path)
+                        .unwrap_or_else(|_| self.file_path.clone());
+                    self.pragma_once.insert(path);
+                }
+            }
+            "line" => self.line_directive(&line[2..], hash_span, false),
+            _ => self.error(
+                format!("invalid preprocessing directive #{name}"),
+                hash_span,
+            ),
+        }
+        vec![]
+    }
+}
+
+/**
+ * Runs the preprocessor over a source file, returning the tokens the parser
+ * should see or every lexical error found in the file and its includes.
+ * Files pulled in by #include are registered in `source_map` and their tokens
+ * carry its file ids
+ */
+pub fn preprocess(
+    src: &str,
+    file_name: &str,
+    include_paths: &[String],
+    source_map: &mut SourceMap,
+) -> Result<Vec<Spanned<Token>>, Vec<LexError>> {
+    let tokens = tokens_from_source(src)?;
+    let mut preprocessor = Preprocessor::new(file_name, include_paths, source_map);
+    let tokens = preprocessor.run(tokens);
+    let mut errors = preprocessor.errors;
+    let mut output = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        match lexer::parser_token(token.token) {
+            Ok(node) => output.push(Spanned::new(node, token.span)),
+            Err(message) => errors.push(LexError::new(message, token.span)),
+        }
+    }
+    match errors.is_empty() {
+        true => Ok(output),
+        false => Err(errors),
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    pub(crate) fn preprocess_str(src: &str) -> Vec<Spanned<Token>> {
+        preprocess(src, "test.c", &[], &mut SourceMap::new("test.c")).unwrap()
+    }
+
+    // message and line:column of every error from preprocessing `src`
+    pub(crate) fn preprocess_errors(src: &str) -> Vec<(String, String)> {
+        preprocess(src, "test.c", &[], &mut SourceMap::new("test.c"))
+            .unwrap_err()
+            .into_iter()
+            .map(|error| (error.message, error.span.to_string()))
+            .collect()
+    }
+
+    pub(crate) fn preprocess_kinds(src: &str) -> Vec<Token> {
+        preprocess_str(src)
+            .into_iter()
+            .map(|token| token.node)
+            .collect()
+    }
+
+    #[test]
+    fn pass_through_text_without_directives() {
+        assert_eq!(
+            preprocess_kinds("int main(void)\n{ return 2; }"),
+            lexer::lex_contents(String::from("int main(void) { return 2; }"))
+                .unwrap()
+                .into_iter()
+                .map(|token| token.node)
+                .collect::<Vec<Token>>()
+        );
+    }
+
+    #[test]
+    fn null_directive_and_pragma_are_ignored() {
+        assert_eq!(
+            preprocess_kinds("#\n#pragma anything at all\nx"),
+            vec![Token::Identifier(String::from("x"))]
+        );
+    }
+
+    #[test]
+    fn conditional_groups() {
+        assert_eq!(
+            preprocess_kinds(
+                "#define A\n#ifdef A\na\n#else\nb\n#endif\n#ifndef A\nc\n#elif 1\nd\n#endif"
+            ),
+            vec![
+                Token::Identifier(String::from("a")),
+                Token::Identifier(String::from("d")),
+            ]
+        );
+    }
+
+    #[test]
+    fn skipped_groups_only_track_nesting() {
+        assert_eq!(
+            preprocess_kinds(
+                "#if 0\n#if 1 / 0\n#error nope\n#frobnicate\n#else\nx\n#endif\n#define Y\n#endif\nY"
+            ),
+            vec![Token::Identifier(String::from("Y"))]
+        );
+    }
+
+    #[test]
+    fn lexical_errors_only_count_outside_skipped_groups() {
+        assert_eq!(
+            preprocess_kinds("#if 0\ndon't use this\n@ $ `\n1.2.3\n#endif\nx"),
+            vec![Token::Identifier(String::from("x"))]
+        );
+        assert_eq!(
+            preprocess_errors("#if 1\ndon't\n#else\n@\n#endif\n#define AT @\nAT"),
+            vec![
+                (
+                    String::from("missing terminating ' character"),
+                    String::from("2:4")
+                ),
+                (String::from("stray '@' in program"), String::from("7:1")),
+            ]
+        );
+    }
+
+    #[test]
+    fn unterminated_conditional() {
+        assert_eq!(
+            preprocess_errors("#if 1\n#ifdef X\n#endif"),
+            vec![(
+                String::from("unterminated conditional directive"),
+                String::from("1:1")
+            )]
+        );
+    }
+
+    #[test]
+    fn endif_without_if() {
+        assert_eq!(
+            preprocess_errors("#endif"),
+            vec![(String::from("#endif without #if"), String::from("1:1"))]
+        );
+    }
+
+    #[test]
+    fn error_directive() {
+        assert_eq!(
+            preprocess_errors("int x;\n#error this is not supported"),
+            vec![(
+                String::from("#error this is not supported"),
+                String::from("2:1")
+            )]
+        );
+    }
+
+    #[test]
+    fn unknown_directive() {
+        assert_eq!(
+            preprocess_errors("#frobnicate\n#ifdef\n#endif"),
+            vec![
+                (
+                    String::from("invalid preprocessing directive #frobnicate"),
+                    String::from("1:1")
+                ),
+                (
+                    String::from("#ifdef expects a macro name"),
+                    String::from("2:1")
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use super::ast::*;
+use super::visit::{
+    VisitorMut, walk_block_mut, walk_expression_mut, walk_for_init_mut, walk_function_mut,
+    walk_statement_mut,
+};
+use crate::core::diagnostic::Diagnostic;
+use crate::core::span::Span;
+use std::collections::HashMap;
+
+struct Declared {
+    unique_name: String,
+    span: Span,
+}
+
+/**
+ * Gives every declared variable a name unique to the program and points each
+ * use of a variable at its declaration. A declaration in an inner block hides
+ * one of the same name from outside until the block ends. Names cannot
+ * contain `.` in C, so `a.0` cannot clash with anything the program declares
+ */
+struct Resolver {
+    scopes: Vec<HashMap<String, Declared>>,
+    declared_count: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Resolver {
+    fn declare(&mut self, name: &str, span: Span) -> String {
+        let scope = self
+            .scopes
+            .last_mut()
+            .expect("declarations are inside a scope");
+        if let Some(previous) = scope.get(name) {
+            self.diagnostics.push(
+                Diagnostic::error(format!("redeclaration of '{name}'"), span).with_note(
+                    format!("previous declaration of '{name}' is here"),
+                    previous.span,
+                ),
+            );
+            return previous.unique_name.clone();
+        }
+        let unique_name = format!("{name}.{}", self.declared_count);
+        self.declared_count += 1;
+        scope.insert(
+            name.to_string(),
+            Declared {
+                unique_name: unique_name.clone(),
+                span,
+            },
+        );
+        unique_name
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Declared> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+}
+
+impl VisitorMut for Resolver {
+    fn visit_function_mut(&mut self, function: &mut Function) {
+        self.scopes.push(HashMap::new());
+        walk_function_mut(self, function);
+        self.scopes.pop();
+    }
+
+    fn visit_statement_mut(&mut self, statement: &mut Statement) {
+        match statement {
+            // the scope of a variable declared in the header of a `for` is
+            // the loop
+            Statement::Compound(..) | Statement::For(..) => {
+                self.scopes.push(HashMap::new());
+                walk_statement_mut(self, statement);
+                self.scopes.pop();
+            }
+            _ => walk_statement_mut(self, statement),
+        }
+    }
+
+    fn visit_for_init_mut(&mut self, init: &mut ForInit) {
+        if let ForInit::Declaration(name, _, span) = init {
+            *name = self.declare(name, *span);
+        }
+        walk_for_init_mut(self, init);
+    }
+
+    // a variable is in scope in its own initializer, as in `int a = a;`
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        if let Block::Declaration(name, _, span) = block {
+            *name = self.declare(name, *span);
+        }
+        walk_block_mut(self, block);
+    }
+
+    fn visit_expression_mut(&mut self, expression: &mut Expression) {
+        match expression {
+            Expression::Var(name, span) => match self.lookup(name) {
+                Some(declared) => *name = declared.unique_name.clone(),
+                None => self.diagnostics.push(Diagnostic::error(
+                    format!("use of undeclared variable '{name}'"),
+                    *span,
+                )),
+            },
+            Expression::Assignment(lvalue, _, _) if !matches!(**lvalue, Expression::Var(..)) => {
+                self.diagnostics.push(Diagnostic::error(
+                    "lvalue required as left operand of assignment",
+                    lvalue.span(),
+                ));
+            }
+            // there are no pointer or array types to give them yet
+            Expression::StringLiteral(_, span) => self.diagnostics.push(Diagnostic::error(
+                "string literals are not supported yet",
+                *span,
+            )),
+            _ => {}
+        }
+        walk_expression_mut(self, expression);
+    }
+}
+
+/**
+ * Checks that every variable is declared once and used only after its
+ * declaration, that only variables are assigned to and that no string
+ * literals are used. Variables are renamed so later stages need not care
+ * about scopes
+ */
+pub fn resolve_program(mut program: Program) -> Result<Program, Vec<Diagnostic>> {
+    let mut resolver = Resolver {
+        scopes: vec![],
+        declared_count: 0,
+        diagnostics: vec![],
+    };
+    resolver.visit_program_mut(&mut program);
+    match resolver.diagnostics.is_empty() {
+        true => Ok(program),
+        false => Err(resolver.diagnostics),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::c::from_lexical::parse_program;
+    use crate::c::lexer::lex_contents;
+    use crate::c::to_source::program_to_string;
+    use crate::core::parser::Parser;
+
+    fn resolve(src: &str) -> Result<Program, Vec<Diagnostic>> {
+        resolve_program(
+            parse_program(&mut Parser::new(lex_contents(src.to_string()).unwrap())).unwrap(),
+        )
+    }
+
+    // message and line:column of every error from resolving `src`
+    fn resolve_errors(src: &str) -> Vec<(String, String)> {
+        resolve(src)
+            .unwrap_err()
+            .into_iter()
+            .map(|diagnostic| (diagnostic.message, diagnostic.span.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn rename_variables() {
+        let program =
+            resolve("int main(void) { int a = 1; int b = a = a + 1; return b; }").unwrap();
+        assert_eq!(
+            program_to_string(&program),
+            "int main(void) {\n    int a.0 = 1;\n    int b.1 = a.0 = a.0 + 1;\n    return b.1;\n}\n"
+        );
+    }
+
+    #[test]
+    fn variable_in_scope_in_own_initializer() {
+        let program = resolve("int main(void) { int a = a; }").unwrap();
+        assert_eq!(
+            program_to_string(&program),
+            "int main(void) {\n    int a.0 = a.0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn inner_declarations_hide_outer_ones() {
+        let program = resolve(
+            "int main(void) { int a = 1; { a = 2; int a = a; { int b = a; } } int b = a; }",
+        )
+        .unwrap();
+        assert_eq!(
+            program_to_string(&program),
+            "int main(void) {\n    int a.0 = 1;\n    {\n        a.0 = 2;\n        int a.1 = a.1;\n        {\n            int b.2 = a.1;\n        }\n    }\n    int b.3 = a.0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn for_declarations_are_scoped_to_the_loop() {
+        let program =
+            resolve("int main(void) { int i; for (int i = i; i; ) { int i; } return i; }").unwrap();
+        assert_eq!(
+            program_to_string(&program),
+            "int main(void) {\n    int i.0;\n    for (int i.1 = i.1; i.1;) {\n        int i.2;\n    }\n    return i.0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn error_on_use_after_block_ends() {
+        assert_eq!(
+            resolve_errors("int main(void) { { int a; } return a; }"),
+            vec![(
+                String::from("use of undeclared variable 'a'"),
+                String::from("1:36")
+            )]
+        );
+    }
+
+    #[test]
+    fn error_on_undeclared_variable() {
+        assert_eq!(
+            resolve_errors("int main(void) { a = 1; int a; return b; }"),
+            vec![
+                (
+                    String::from("use of undeclared variable 'a'"),
+                    String::from("1:18")
+                ),
+                (
+                    String::from("use of undeclared variable 'b'"),
+                    String::from("1:39")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn error_on_redeclaration() {
+        let diagnostics = resolve("int main(void) { int a;\n  int a = 2; }").unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "redeclaration of 'a'");
+        assert_eq!(diagnostics[0].span.to_string(), "2:3");
+        assert_eq!(diagnostics[0].notes[0].span.to_string(), "1:18");
+    }
+
+    #[test]
+    fn error_on_string_literal() {
+        assert_eq!(
+            resolve_errors("int main(void) { return \"abc\"; }"),
+            vec![(
+                String::from("string literals are not supported yet"),
+                String::from("1:25")
+            )]
+        );
+    }
+
+    #[test]
+    fn error_on_invalid_lvalue() {
+        assert_eq!(
+            resolve_errors("int main(void) { int a; 2 = a; -a = 1; return a = 1; }"),
+            vec![
+                (
+                    String::from("lvalue required as left operand of assignment"),
+                    String::from("1:25")
+                ),
+                (
+                    String::from("lvalue required as left operand of assignment"),
+                    String::from("1:32")
+                ),
+            ]
+        );
+    }
+}
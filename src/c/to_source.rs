@@ -0,0 +1,577 @@
+use super::ast::*;
+
+const INDENT: &str = "    ";
+
+fn unary_op_to_string(operator: &UnaryOperator) -> &'static str {
+    match operator {
+        UnaryOperator::Negation => "-",
+        UnaryOperator::Complement => "~",
+        UnaryOperator::Not => "!",
+    }
+}
+
+fn binary_op_to_string(operator: &BinaryOperator) -> &'static str {
+    match operator {
+        BinaryOperator::Add => "+",
+        BinaryOperator::Subtract => "-",
+        BinaryOperator::Multiply => "*",
+        BinaryOperator::Divide => "/",
+        BinaryOperator::Modulo => "%",
+        BinaryOperator::LeftShift => "<<",
+        BinaryOperator::RightShift => ">>",
+        BinaryOperator::BitwiseAnd => "&",
+        BinaryOperator::BitwiseXor => "^",
+        BinaryOperator::BitwiseOr => "|",
+        BinaryOperator::LogicalAnd => "&&",
+        BinaryOperator::LogicalOr => "||",
+        BinaryOperator::IsEqual => "==",
+        BinaryOperator::NotEqual => "!=",
+        BinaryOperator::LessThan => "<",
+        BinaryOperator::LessThanOrEqual => "<=",
+        BinaryOperator::GreaterThan => ">",
+        BinaryOperator::GreaterThanOrEqual => ">=",
+        BinaryOperator::Equal => "=",
+    }
+}
+
+/**
+ * Escapes the bytes of a character or string literal. Anything that is not
+ * printable is written as a 3 digit octal escape, which unlike hex escapes
+ * cannot swallow the characters following it
+ */
+fn escape_literal(bytes: &[u8], quote: u8) -> String {
+    let mut escaped = String::new();
+    for &byte in bytes {
+        match byte {
+            b'\n' => escaped.push_str("\\n"),
+            b'\t' => escaped.push_str("\\t"),
+            b'\r' => escaped.push_str("\\r"),
+            b'\\' => escaped.push_str("\\\\"),
+            _ if byte == quote => {
+                escaped.push('\\');
+                escaped.push(byte as char);
+            }
+            b' '..=b'~' => escaped.push(byte as char),
+            _ => escaped.push_str(&format!("\\{byte:03o}")),
+        }
+    }
+    escaped
+}
+
+/**
+ * Precedence of an expression when it is the operand of an operator. Unary
+ * expressions and primary expressions bind tighter than any binary operator
+ */
+fn expression_precedence(expression: &Expression) -> Option<i32> {
+    match expression {
+        Expression::Binary(operator, _, _, _) => Some(binary_operator_precedence(operator)),
+        Expression::Assignment(_, _, _) => Some(binary_operator_precedence(&BinaryOperator::Equal)),
+        Expression::Conditional(_, _, _, _) => Some(CONDITIONAL_PRECEDENCE),
+        _ => None,
+    }
+}
+
+// binary operators are parsed left associative, so the right operand needs
+// parentheses even when it has the same precedence. Assignment is the other
+// way around
+fn binary_to_string(operator: &BinaryOperator, left: &Expression, right: &Expression) -> String {
+    let precedence = binary_operator_precedence(operator);
+    let right_associative = *operator == BinaryOperator::Equal;
+    let left = match expression_precedence(left) {
+        Some(left_precedence)
+            if left_precedence < precedence
+                || (right_associative && left_precedence == precedence) =>
+        {
+            format!("({})", expression_to_string(left))
+        }
+        _ => expression_to_string(left),
+    };
+    let right = match expression_precedence(right) {
+        Some(right_precedence)
+            if right_precedence < precedence
+                || (!right_associative && right_precedence == precedence) =>
+        {
+            format!("({})", expression_to_string(right))
+        }
+        _ => expression_to_string(right),
+    };
+    format!("{left} {} {right}", binary_op_to_string(operator))
+}
+
+fn expression_to_string(expression: &Expression) -> String {
+    match expression {
+        Expression::Constant(value, _) => value.to_string(),
+        Expression::CharLiteral(value, _) => format!("'{}'", escape_literal(&[*value], b'\'')),
+        Expression::StringLiteral(bytes, _) => format!("\"{}\"", escape_literal(bytes, b'"')),
+        Expression::Var(name, _) => name.clone(),
+        Expression::Unary(operator, operand, _) => {
+            // `- -x` must not become the `--` operator
+            let needs_parentheses = expression_precedence(operand).is_some()
+                || matches!(
+                    (operator, operand.as_ref()),
+                    (
+                        UnaryOperator::Negation,
+                        Expression::Unary(UnaryOperator::Negation, _, _)
+                    )
+                );
+            match needs_parentheses {
+                true => format!(
+                    "{}({})",
+                    unary_op_to_string(operator),
+                    expression_to_string(operand)
+                ),
+                false => format!(
+                    "{}{}",
+                    unary_op_to_string(operator),
+                    expression_to_string(operand)
+                ),
+            }
+        }
+        Expression::Binary(operator, left, right, _) => binary_to_string(operator, left, right),
+        Expression::Assignment(left, right, _) => {
+            binary_to_string(&BinaryOperator::Equal, left, right)
+        }
+        Expression::Conditional(condition, then, otherwise, _) => {
+            conditional_to_string(condition, then, otherwise)
+        }
+    }
+}
+
+// `?:` is right associative, so only the condition needs parentheses around
+// another `?:`. The middle operand never needs any
+fn conditional_to_string(
+    condition: &Expression,
+    then: &Expression,
+    otherwise: &Expression,
+) -> String {
+    let condition = match expression_precedence(condition) {
+        Some(precedence) if precedence <= CONDITIONAL_PRECEDENCE => {
+            format!("({})", expression_to_string(condition))
+        }
+        _ => expression_to_string(condition),
+    };
+    let otherwise = match expression_precedence(otherwise) {
+        Some(precedence) if precedence < CONDITIONAL_PRECEDENCE => {
+            format!("({})", expression_to_string(otherwise))
+        }
+        _ => expression_to_string(otherwise),
+    };
+    format!("{condition} ? {} : {otherwise}", expression_to_string(then))
+}
+
+// whether an `else` after the statement would be taken by an `if` inside it
+fn ends_with_open_if(statement: &Statement) -> bool {
+    match statement {
+        Statement::If(_, _, None, _) => true,
+        Statement::If(_, _, Some(otherwise), _) => ends_with_open_if(otherwise),
+        Statement::While(_, body, _, _)
+        | Statement::For(_, _, _, body, _, _)
+        | Statement::Switch(_, body, _, _)
+        | Statement::Case(_, body, _, _)
+        | Statement::Default(body, _, _) => ends_with_open_if(body),
+        _ => false,
+    }
+}
+
+fn blocks_to_string(blocks: &[Block], depth: usize) -> String {
+    blocks
+        .iter()
+        .map(|block| block_to_string(block, depth))
+        .collect()
+}
+
+/**
+ * The body of an `if`, `else`, loop or switch at `depth`, to follow the keyword or
+ * the condition. Compound statements open their brace on the same line, anything
+ * else goes on its own line one level deeper. No trailing newline
+ */
+fn body_to_string(statement: &Statement, depth: usize) -> String {
+    let indent = INDENT.repeat(depth);
+    match statement {
+        Statement::Compound(blocks, _) => {
+            format!(" {{\n{}{indent}}}", blocks_to_string(blocks, depth + 1))
+        }
+        _ => format!(
+            "\n{}",
+            statement_to_string(statement, depth + 1).trim_end_matches('\n')
+        ),
+    }
+}
+
+// the lines of a statement nested `depth` levels into the function body
+fn statement_to_string(statement: &Statement, depth: usize) -> String {
+    let indent = INDENT.repeat(depth);
+    match statement {
+        Statement::Return(expression, _) => {
+            format!("{indent}return {};\n", expression_to_string(expression))
+        }
+        Statement::Expression(expression, _) => {
+            format!("{indent}{};\n", expression_to_string(expression))
+        }
+        Statement::Null(_) => format!("{indent};\n"),
+        Statement::Switch(control, body, _, _) => format!(
+            "{indent}switch ({}){}\n",
+            expression_to_string(control),
+            body_to_string(body, depth)
+        ),
+        // case labels line up with the statements around them
+        Statement::Case(value, body, _, _) => format!(
+            "{indent}case {}:\n{}",
+            expression_to_string(value),
+            statement_to_string(body, depth)
+        ),
+        Statement::Default(body, _, _) => {
+            format!("{indent}default:\n{}", statement_to_string(body, depth))
+        }
+        Statement::Break(_, _) => format!("{indent}break;\n"),
+        Statement::Continue(_, _) => format!("{indent}continue;\n"),
+        Statement::While(condition, body, _, _) => format!(
+            "{indent}while ({}){}\n",
+            expression_to_string(condition),
+            body_to_string(body, depth)
+        ),
+        Statement::DoWhile(body, condition, _, _) => {
+            let separator = match body.as_ref() {
+                Statement::Compound(..) => String::from(" "),
+                _ => format!("\n{indent}"),
+            };
+            format!(
+                "{indent}do{}{separator}while ({});\n",
+                body_to_string(body, depth),
+                expression_to_string(condition)
+            )
+        }
+        Statement::For(init, condition, post, body, _, _) => {
+            let init = match init.as_ref() {
+                ForInit::Declaration(name, None, _) => format!("int {name};"),
+                ForInit::Declaration(name, Some(initializer), _) => {
+                    format!("int {name} = {};", expression_to_string(initializer))
+                }
+                ForInit::Expression(None) => String::from(";"),
+                ForInit::Expression(Some(expression)) => {
+                    format!("{};", expression_to_string(expression))
+                }
+            };
+            let condition = match condition {
+                Some(condition) => format!(" {};", expression_to_string(condition)),
+                None => String::from(";"),
+            };
+            let post = match post {
+                Some(post) => format!(" {}", expression_to_string(post)),
+                None => String::new(),
+            };
+            format!(
+                "{indent}for ({init}{condition}{post}){}\n",
+                body_to_string(body, depth)
+            )
+        }
+        Statement::Compound(blocks, _) => {
+            format!(
+                "{indent}{{\n{}{indent}}}\n",
+                blocks_to_string(blocks, depth + 1)
+            )
+        }
+        Statement::If(condition, then, None, _) => format!(
+            "{indent}if ({}){}\n",
+            expression_to_string(condition),
+            body_to_string(then, depth)
+        ),
+        Statement::If(condition, then, Some(otherwise), _) => {
+            // `if (a) if (b) x; else y;` would give the `else` to the inner
+            // `if`, so it is kept out with braces
+            let separator = match then.as_ref() {
+                Statement::Compound(..) => String::from(" "),
+                _ if ends_with_open_if(then) => String::from(" "),
+                _ => format!("\n{indent}"),
+            };
+            let then = match ends_with_open_if(then) {
+                true => format!(" {{\n{}{indent}}}", statement_to_string(then, depth + 1)),
+                false => body_to_string(then, depth),
+            };
+            let otherwise = match otherwise.as_ref() {
+                // keep `else if` chains flat
+                otherwise @ Statement::If(..) => {
+                    format!(" {}", statement_to_string(otherwise, depth).trim_start())
+                }
+                otherwise => format!("{}\n", body_to_string(otherwise, depth)),
+            };
+            format!(
+                "{indent}if ({}){then}{separator}else{otherwise}",
+                expression_to_string(condition)
+            )
+        }
+    }
+}
+
+fn block_to_string(block: &Block, depth: usize) -> String {
+    let indent = INDENT.repeat(depth);
+    match block {
+        Block::Statement(statement) => statement_to_string(statement, depth),
+        Block::Declaration(name, None, _) => format!("{indent}int {name};\n"),
+        Block::Declaration(name, Some(initializer), _) => {
+            format!(
+                "{indent}int {name} = {};\n",
+                expression_to_string(initializer)
+            )
+        }
+    }
+}
+
+fn function_to_string(function: &Function) -> String {
+    match function {
+        Function::Function(name, blocks, _) => {
+            format!("int {name}(void) {{\n{}}}\n", blocks_to_string(blocks, 1))
+        }
+    }
+}
+
+/**
+ * Renders a program as C source, with a consistent layout and only the
+ * parentheses needed to parse back into the same AST
+ */
+pub fn program_to_string(program: &Program) -> String {
+    match program {
+        Program::Program(function) => function_to_string(function),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::c::from_lexical::parse_program;
+    use crate::c::lexer::lex_contents;
+    use crate::c::preprocess::preprocess;
+    use crate::c::visit::*;
+    use crate::core::parser::Parser;
+    use crate::core::source_map::SourceMap;
+    use crate::core::span::Span;
+    use std::fs;
+
+    fn parse(src: &str) -> Program {
+        parse_program(&mut Parser::new(lex_contents(src.to_string()).unwrap())).unwrap()
+    }
+
+    // printed source parses back with different spans, only compare the rest
+    struct EraseSpans;
+
+    fn erased() -> Span {
+        Span::new(0, 0, 0, 0)
+    }
+
+    impl VisitorMut for EraseSpans {
+        fn visit_function_mut(&mut self, function: &mut Function) {
+            let Function::Function(_, _, span) = function;
+            *span = erased();
+            walk_function_mut(self, function);
+        }
+
+        fn visit_block_mut(&mut self, block: &mut Block) {
+            if let Block::Declaration(_, _, span) = block {
+                *span = erased();
+            }
+            walk_block_mut(self, block);
+        }
+
+        fn visit_statement_mut(&mut self, statement: &mut Statement) {
+            match statement {
+                Statement::Return(_, span)
+                | Statement::Expression(_, span)
+                | Statement::If(_, _, _, span)
+                | Statement::Compound(_, span)
+                | Statement::Break(_, span)
+                | Statement::Continue(_, span)
+                | Statement::While(_, _, _, span)
+                | Statement::DoWhile(_, _, _, span)
+                | Statement::For(_, _, _, _, _, span)
+                | Statement::Switch(_, _, _, span)
+                | Statement::Case(_, _, _, span)
+                | Statement::Default(_, _, span)
+                | Statement::Null(span) => *span = erased(),
+            }
+            walk_statement_mut(self, statement);
+        }
+
+        fn visit_for_init_mut(&mut self, init: &mut ForInit) {
+            if let ForInit::Declaration(_, _, span) = init {
+                *span = erased();
+            }
+            walk_for_init_mut(self, init);
+        }
+
+        fn visit_expression_mut(&mut self, expression: &mut Expression) {
+            match expression {
+                Expression::Constant(_, span)
+                | Expression::CharLiteral(_, span)
+                | Expression::StringLiteral(_, span)
+                | Expression::Var(_, span)
+                | Expression::Unary(_, _, span)
+                | Expression::Binary(_, _, _, span)
+                | Expression::Assignment(_, _, span)
+                | Expression::Conditional(_, _, _, span) => *span = erased(),
+            }
+            walk_expression_mut(self, expression);
+        }
+    }
+
+    fn erase_spans(mut program: Program) -> Program {
+        EraseSpans.visit_program_mut(&mut program);
+        program
+    }
+
+    fn assert_round_trip(program: Program) {
+        let printed = program_to_string(&program);
+        assert_eq!(
+            erase_spans(parse(&printed)),
+            erase_spans(program),
+            "{printed}"
+        );
+    }
+
+    #[test]
+    fn print_program() {
+        let program = parse(
+            "int main ( void ) { int a=1 ; ; a = (a+2)*3 - -(-a) ; return (a << 1) < 2 == !~a ; }",
+        );
+        assert_eq!(
+            program_to_string(&program),
+            "int main(void) {\n    int a = 1;\n    ;\n    a = (a + 2) * 3 - -(-a);\n    return a << 1 < 2 == !~a;\n}\n"
+        );
+    }
+
+    #[test]
+    fn only_emit_needed_parentheses() {
+        for (src, expected) in [
+            ("(1 + 2) + 3", "1 + 2 + 3"),
+            ("1 + (2 + 3)", "1 + (2 + 3)"),
+            ("1 - (2 * 3)", "1 - 2 * 3"),
+            ("(1 || 2) && 3", "(1 || 2) && 3"),
+            ("-(1 + 2)", "-(1 + 2)"),
+            ("((a = 1))", "a = 1"),
+            ("a = (b = 1)", "a = b = 1"),
+            ("(a = b) = 1", "(a = b) = 1"),
+            ("a ? b : (c ? d : e)", "a ? b : c ? d : e"),
+            ("(a ? b : c) ? d : e", "(a ? b : c) ? d : e"),
+            ("a ? (b = 1) : (c = 2)", "a ? b = 1 : (c = 2)"),
+            ("(a || b) ? c : d", "a || b ? c : d"),
+            ("1 + (a ? b : c)", "1 + (a ? b : c)"),
+        ] {
+            let program = parse(&format!("int main(void) {{ return {src}; }}"));
+            assert_eq!(
+                program_to_string(&program),
+                format!("int main(void) {{\n    return {expected};\n}}\n")
+            );
+        }
+    }
+
+    #[test]
+    fn print_if_statements() {
+        let program =
+            parse("int main(void) { if (a) if (b) return 1; else ; else if (c) a = 2; else ; }");
+        assert_eq!(
+            program_to_string(&program),
+            "int main(void) {\n    if (a)\n        if (b)\n            return 1;\n        else\n            ;\n    else if (c)\n        a = 2;\n    else\n        ;\n}\n"
+        );
+        assert_round_trip(program);
+    }
+
+    #[test]
+    fn print_compound_statements() {
+        let program = parse(
+            "int main(void) { { int a; { } } if (a) { a = 1; } else { } if (a) if (b) c; else d; }",
+        );
+        assert_eq!(
+            program_to_string(&program),
+            "int main(void) {\n    {\n        int a;\n        {\n        }\n    }\n    if (a) {\n        a = 1;\n    } else {\n    }\n    if (a)\n        if (b)\n            c;\n        else\n            d;\n}\n"
+        );
+        assert_round_trip(program);
+    }
+
+    #[test]
+    fn print_loops() {
+        let program = parse(
+            "int main(void) { while (a) { break; } do a = a - 1; while (a); do { continue; } while (1); for (;;) ; for (int i = 0; i < 3; i = i + 1) a = i; for (a = 1; ; ) { } }",
+        );
+        assert_eq!(
+            program_to_string(&program),
+            "int main(void) {\n    while (a) {\n        break;\n    }\n    do\n        a = a - 1;\n    while (a);\n    do {\n        continue;\n    } while (1);\n    for (;;)\n        ;\n    for (int i = 0; i < 3; i = i + 1)\n        a = i;\n    for (a = 1;;) {\n    }\n}\n"
+        );
+        assert_round_trip(program);
+    }
+
+    #[test]
+    fn print_switch() {
+        let program = parse(
+            "int main(void) { switch (a) { case 1: case 2: a = 3; break; default: return a; } switch (a) case 0: ; }",
+        );
+        assert_eq!(
+            program_to_string(&program),
+            "int main(void) {\n    switch (a) {\n        case 1:\n        case 2:\n        a = 3;\n        break;\n        default:\n        return a;\n    }\n    switch (a)\n        case 0:\n        ;\n}\n"
+        );
+        assert_round_trip(program);
+    }
+
+    #[test]
+    fn keep_else_from_inner_if_with_braces() {
+        // the parser always gives `else` to the inner `if`, so build the tree
+        let span = Span::new(0, 0, 0, 0);
+        let var = |name: &str| Expression::Var(String::from(name), span);
+        let inner = Statement::If(
+            var("b"),
+            Box::new(Statement::Expression(var("c"), span)),
+            None,
+            span,
+        );
+        let outer = Statement::If(
+            var("a"),
+            Box::new(inner),
+            Some(Box::new(Statement::Expression(var("d"), span))),
+            span,
+        );
+        let program = Program::Program(Function::Function(
+            String::from("main"),
+            vec![Block::Statement(outer)],
+            span,
+        ));
+        assert_eq!(
+            program_to_string(&program),
+            "int main(void) {\n    if (a) {\n        if (b)\n            c;\n    } else\n        d;\n}\n"
+        );
+    }
+
+    #[test]
+    fn escape_literals() {
+        let program = parse("int main(void) { 'a'; '\\''; \"a\\n\\\"b\\x01\" \"1\"; }");
+        assert_eq!(
+            program_to_string(&program),
+            "int main(void) {\n    'a';\n    '\\'';\n    \"a\\n\\\"b\\0011\";\n}\n"
+        );
+        assert_round_trip(program);
+    }
+
+    #[test]
+    fn round_trip_every_fixture() {
+        let mut fixtures: Vec<_> = fs::read_dir("tests/fixtures/input")
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "c" || ext == "i"))
+            .collect();
+        fixtures.sort();
+        let mut round_tripped = 0;
+        for path in fixtures {
+            let src = fs::read_to_string(&path).unwrap();
+            let file_name = path.display().to_string();
+            let mut source_map = SourceMap::new(&file_name);
+            let include_paths = [String::from("tests/fixtures/input/include")];
+            // the fixtures for bad input do not parse in the first place
+            let Ok(tokens) = preprocess(&src, &file_name, &include_paths, &mut source_map) else {
+                continue;
+            };
+            let Ok(program) = parse_program(&mut Parser::new(tokens)) else {
+                continue;
+            };
+            assert_round_trip(program);
+            round_tripped += 1;
+        }
+        assert!(round_tripped >= 6, "only {round_tripped} fixtures parsed");
+    }
+}
@@ -29,7 +29,9 @@ fn translate_binary_operator(op: c::ast::BinaryOperator) -> tacky::ast::BinaryOp
         c::ast::BinaryOperator::LessThanOrEqual => tacky::ast::BinaryOperator::LessThanEqual,
         c::ast::BinaryOperator::GreaterThan => tacky::ast::BinaryOperator::GreaterThan,
         c::ast::BinaryOperator::GreaterThanOrEqual => tacky::ast::BinaryOperator::GreaterThanEqual,
-        c::ast::BinaryOperator::Equal => todo!(),
+        c::ast::BinaryOperator::Equal => {
+            unreachable!("assignments are parsed into Expression::Assignment")
+        }
     }
 }
 
@@ -69,13 +71,37 @@ fn name_binary_result(
     format!("{result_type}Of{v1_name}And{v2_name}")
 }
 
-fn label_binary_step(
-    op: &tacky::ast::BinaryOperator,
-    v1: &tacky::ast::Value,
-    v2: &tacky::ast::Value,
-    step_name: &str,
-) -> String {
-    format!("{}_{}", name_binary_result(op, v1, v2), step_name)
+// the results of operations that need jumps are always temporaries, whose
+// index makes the labels unique
+fn label_step(dst: &tacky::ast::Value, step_name: &str) -> String {
+    match dst {
+        tacky::ast::Value::Variable(name, i) => format!("{name}.{i}_{step_name}"),
+        tacky::ast::Value::Constant(_) => unreachable!("results are stored in variables"),
+    }
+}
+
+/**
+ * Hands out the temporaries and labels of a function. Every one gets an index
+ * nothing else in the function has, so its name only needs to describe it
+ */
+struct Temporaries {
+    last_index: i32,
+}
+
+impl Temporaries {
+    fn new() -> Self {
+        Temporaries { last_index: 0 }
+    }
+
+    fn make(&mut self, name: String) -> tacky::ast::Value {
+        self.last_index += 1;
+        tacky::ast::Value::Variable(name, self.last_index)
+    }
+
+    fn label(&mut self, name: &str) -> String {
+        self.last_index += 1;
+        format!("{name}.{}", self.last_index)
+    }
 }
 
 fn generate_binop_instructions(
@@ -91,34 +117,16 @@ fn generate_binop_instructions(
             inner1,
             vec![tacky::ast::Instruction::JumpIfZero(
                 inner1_dst.clone(),
-                label_binary_step(&tacky_op, &inner1_dst, &inner2_dst, "FALSE"),
+                label_step(&dst, "FALSE"),
             )],
             inner2,
             vec![
-                tacky::ast::Instruction::JumpIfZero(
-                    inner2_dst.clone(),
-                    label_binary_step(&tacky_op, &inner1_dst, &inner2_dst, "FALSE"),
-                ),
+                tacky::ast::Instruction::JumpIfZero(inner2_dst.clone(), label_step(&dst, "FALSE")),
                 tacky::ast::Instruction::Copy(tacky::ast::Value::Constant(1), dst.clone()),
-                tacky::ast::Instruction::Jump(label_binary_step(
-                    &tacky_op,
-                    &inner1_dst,
-                    &inner2_dst,
-                    "END",
-                )),
-                tacky::ast::Instruction::Label(label_binary_step(
-                    &tacky_op,
-                    &inner1_dst,
-                    &inner2_dst,
-                    "FALSE",
-                )),
-                tacky::ast::Instruction::Copy(tacky::ast::Value::Constant(0), dst),
-                tacky::ast::Instruction::Label(label_binary_step(
-                    &tacky_op,
-                    &inner1_dst,
-                    &inner2_dst,
-                    "END",
-                )),
+                tacky::ast::Instruction::Jump(label_step(&dst, "END")),
+                tacky::ast::Instruction::Label(label_step(&dst, "FALSE")),
+                tacky::ast::Instruction::Copy(tacky::ast::Value::Constant(0), dst.clone()),
+                tacky::ast::Instruction::Label(label_step(&dst, "END")),
             ],
         ]
         .concat(),
@@ -126,34 +134,19 @@ fn generate_binop_instructions(
             inner1,
             vec![tacky::ast::Instruction::JumpIfNotZero(
                 inner1_dst.clone(),
-                label_binary_step(&tacky_op, &inner1_dst, &inner2_dst, "TRUE"),
+                label_step(&dst, "TRUE"),
             )],
             inner2,
             vec![
                 tacky::ast::Instruction::JumpIfNotZero(
                     inner2_dst.clone(),
-                    label_binary_step(&tacky_op, &inner1_dst, &inner2_dst, "TRUE"),
+                    label_step(&dst, "TRUE"),
                 ),
                 tacky::ast::Instruction::Copy(tacky::ast::Value::Constant(0), dst.clone()),
-                tacky::ast::Instruction::Jump(label_binary_step(
-                    &tacky_op,
-                    &inner1_dst,
-                    &inner2_dst,
-                    "END",
-                )),
-                tacky::ast::Instruction::Label(label_binary_step(
-                    &tacky_op,
-                    &inner1_dst,
-                    &inner2_dst,
-                    "TRUE",
-                )),
-                tacky::ast::Instruction::Copy(tacky::ast::Value::Constant(1), dst),
-                tacky::ast::Instruction::Label(label_binary_step(
-                    &tacky_op,
-                    &inner1_dst,
-                    &inner2_dst,
-                    "END",
-                )),
+                tacky::ast::Instruction::Jump(label_step(&dst, "END")),
+                tacky::ast::Instruction::Label(label_step(&dst, "TRUE")),
+                tacky::ast::Instruction::Copy(tacky::ast::Value::Constant(1), dst.clone()),
+                tacky::ast::Instruction::Label(label_step(&dst, "END")),
             ],
         ]
         .concat(),
@@ -173,18 +166,24 @@ fn generate_binop_instructions(
 
 fn translate_expression(
     expr: c::ast::Expression,
+    temporaries: &mut Temporaries,
 ) -> (Vec<tacky::ast::Instruction>, tacky::ast::Value) {
     match expr {
-        c::ast::Expression::Constant(value) => (vec![], tacky::ast::Value::Constant(value)),
-        c::ast::Expression::Unary(op, inner_expr) => {
-            let (mut inner_instructions, inner_value) = translate_expression(*inner_expr);
+        c::ast::Expression::Constant(value, _) => (vec![], tacky::ast::Value::Constant(value)),
+        // char is signed on x86-64, so '\xff' is -1
+        c::ast::Expression::CharLiteral(value, _) => {
+            (vec![], tacky::ast::Value::Constant(value as i8 as i32))
+        }
+        c::ast::Expression::StringLiteral(..) => {
+            unreachable!("resolve rejects string literals")
+        }
+        c::ast::Expression::Var(name, _) => (vec![], tacky::ast::Value::Variable(name, 0)),
+        c::ast::Expression::Unary(op, inner_expr, _) => {
+            let (mut inner_instructions, inner_value) =
+                translate_expression(*inner_expr, temporaries);
             let variable = match inner_value {
-                tacky::ast::Value::Constant(val) => {
-                    tacky::ast::Value::Variable(format!("unary{:?}", op), val)
-                }
-                tacky::ast::Value::Variable(ref name, i) => {
-                    tacky::ast::Value::Variable(name.clone(), i + 1)
-                }
+                tacky::ast::Value::Constant(_) => temporaries.make(format!("unary{:?}", op)),
+                tacky::ast::Value::Variable(ref name, _) => temporaries.make(name.clone()),
             };
             inner_instructions.push(tacky::ast::Instruction::Unary(
                 translate_unary_operator(op),
@@ -193,14 +192,15 @@ fn translate_expression(
             ));
             (inner_instructions, variable)
         }
-        c::ast::Expression::Binary(op, v1, v2) => {
-            let (inner_instructions_v1, inner_value_v1) = translate_expression(*v1);
-            let (inner_instructions_v2, inner_value_v2) = translate_expression(*v2);
+        c::ast::Expression::Binary(op, v1, v2, _) => {
+            let (inner_instructions_v1, inner_value_v1) = translate_expression(*v1, temporaries);
+            let (inner_instructions_v2, inner_value_v2) = translate_expression(*v2, temporaries);
             let tacky_op = translate_binary_operator(op);
-            let dst = tacky::ast::Value::Variable(
-                name_binary_result(&tacky_op, &inner_value_v1, &inner_value_v2),
-                0,
-            );
+            let dst = temporaries.make(name_binary_result(
+                &tacky_op,
+                &inner_value_v1,
+                &inner_value_v2,
+            ));
             let instructions = generate_binop_instructions(
                 tacky_op,
                 inner_instructions_v1,
@@ -211,37 +211,276 @@ fn translate_expression(
             );
             (instructions, dst)
         }
-        _ => todo!(),
+        c::ast::Expression::Assignment(lvalue, rvalue, _) => {
+            let c::ast::Expression::Var(name, _) = *lvalue else {
+                unreachable!("resolve_program rejects assignments to anything but variables")
+            };
+            let (mut instructions, value) = translate_expression(*rvalue, temporaries);
+            let variable = tacky::ast::Value::Variable(name, 0);
+            instructions.push(tacky::ast::Instruction::Copy(value, variable.clone()));
+            (instructions, variable)
+        }
+        c::ast::Expression::Conditional(condition, then, otherwise, _) => {
+            let (condition_instructions, condition_value) =
+                translate_expression(*condition, temporaries);
+            let (then_instructions, then_value) = translate_expression(*then, temporaries);
+            let (otherwise_instructions, otherwise_value) =
+                translate_expression(*otherwise, temporaries);
+            let dst = temporaries.make(String::from("Conditional"));
+            let instructions = [
+                condition_instructions,
+                vec![tacky::ast::Instruction::JumpIfZero(
+                    condition_value,
+                    label_step(&dst, "ELSE"),
+                )],
+                then_instructions,
+                vec![
+                    tacky::ast::Instruction::Copy(then_value, dst.clone()),
+                    tacky::ast::Instruction::Jump(label_step(&dst, "END")),
+                    tacky::ast::Instruction::Label(label_step(&dst, "ELSE")),
+                ],
+                otherwise_instructions,
+                vec![
+                    tacky::ast::Instruction::Copy(otherwise_value, dst.clone()),
+                    tacky::ast::Instruction::Label(label_step(&dst, "END")),
+                ],
+            ]
+            .concat();
+            (instructions, dst)
+        }
     }
 }
 
-fn translate_statement(statement: c::ast::Statement) -> Vec<tacky::ast::Instruction> {
+fn expect_loop_label(label: Option<String>) -> String {
+    label.expect("label_loops labels every loop, break, continue and case")
+}
+
+/**
+ * Jumps to the case matching the value of the controlling expression, trying
+ * each in turn. A jump table could replace this chain for switches with many
+ * densely packed cases, without changing how the body is lowered
+ */
+fn generate_switch_dispatch(
+    control: tacky::ast::Value,
+    labels: c::ast::SwitchLabels,
+    temporaries: &mut Temporaries,
+) -> Vec<tacky::ast::Instruction> {
+    let mut instructions = vec![];
+    for (value, case_label) in labels.cases {
+        let matches = temporaries.make(String::from("SwitchCase"));
+        instructions.push(tacky::ast::Instruction::Binary(
+            tacky::ast::BinaryOperator::Equal,
+            control.clone(),
+            tacky::ast::Value::Constant(value),
+            matches.clone(),
+        ));
+        instructions.push(tacky::ast::Instruction::JumpIfNotZero(matches, case_label));
+    }
+    // with no default, a value matching no case skips the whole body
+    let fallback = labels
+        .default
+        .unwrap_or_else(|| format!("{}_BREAK", labels.label));
+    instructions.push(tacky::ast::Instruction::Jump(fallback));
+    instructions
+}
+
+fn translate_statement(
+    statement: c::ast::Statement,
+    temporaries: &mut Temporaries,
+) -> Vec<tacky::ast::Instruction> {
     match statement {
-        c::ast::Statement::Return(expr) => {
-            let (inner_instructions, value) = translate_expression(expr);
+        c::ast::Statement::Return(expr, _) => {
+            let (mut instructions, value) = translate_expression(expr, temporaries);
+            instructions.push(tacky::ast::Instruction::Return(value));
+            instructions
+        }
+        c::ast::Statement::Expression(expr, _) => translate_expression(expr, temporaries).0,
+        c::ast::Statement::If(condition, then, None, _) => {
+            let end_label = format!("{}_END", temporaries.label("If"));
+            let (condition_instructions, condition_value) =
+                translate_expression(condition, temporaries);
+            [
+                condition_instructions,
+                vec![tacky::ast::Instruction::JumpIfZero(
+                    condition_value,
+                    end_label.clone(),
+                )],
+                translate_statement(*then, temporaries),
+                vec![tacky::ast::Instruction::Label(end_label)],
+            ]
+            .concat()
+        }
+        c::ast::Statement::If(condition, then, Some(otherwise), _) => {
+            let label = temporaries.label("If");
+            let else_label = format!("{label}_ELSE");
+            let end_label = format!("{label}_END");
+            let (condition_instructions, condition_value) =
+                translate_expression(condition, temporaries);
+            [
+                condition_instructions,
+                vec![tacky::ast::Instruction::JumpIfZero(
+                    condition_value,
+                    else_label.clone(),
+                )],
+                translate_statement(*then, temporaries),
+                vec![
+                    tacky::ast::Instruction::Jump(end_label.clone()),
+                    tacky::ast::Instruction::Label(else_label),
+                ],
+                translate_statement(*otherwise, temporaries),
+                vec![tacky::ast::Instruction::Label(end_label)],
+            ]
+            .concat()
+        }
+        c::ast::Statement::Compound(blocks, _) => blocks
+            .into_iter()
+            .flat_map(|block| translate_block(block, temporaries))
+            .collect(),
+        c::ast::Statement::Break(label, _) => vec![tacky::ast::Instruction::Jump(format!(
+            "{}_BREAK",
+            expect_loop_label(label)
+        ))],
+        c::ast::Statement::Continue(label, _) => vec![tacky::ast::Instruction::Jump(format!(
+            "{}_CONTINUE",
+            expect_loop_label(label)
+        ))],
+        c::ast::Statement::While(condition, body, label, _) => {
+            let label = expect_loop_label(label);
+            let (condition_instructions, condition_value) =
+                translate_expression(condition, temporaries);
+            [
+                vec![tacky::ast::Instruction::Label(format!("{label}_CONTINUE"))],
+                condition_instructions,
+                vec![tacky::ast::Instruction::JumpIfZero(
+                    condition_value,
+                    format!("{label}_BREAK"),
+                )],
+                translate_statement(*body, temporaries),
+                vec![
+                    tacky::ast::Instruction::Jump(format!("{label}_CONTINUE")),
+                    tacky::ast::Instruction::Label(format!("{label}_BREAK")),
+                ],
+            ]
+            .concat()
+        }
+        c::ast::Statement::DoWhile(body, condition, label, _) => {
+            let label = expect_loop_label(label);
+            let body = translate_statement(*body, temporaries);
+            let (condition_instructions, condition_value) =
+                translate_expression(condition, temporaries);
+            [
+                vec![tacky::ast::Instruction::Label(format!("{label}_START"))],
+                body,
+                vec![tacky::ast::Instruction::Label(format!("{label}_CONTINUE"))],
+                condition_instructions,
+                vec![
+                    tacky::ast::Instruction::JumpIfNotZero(
+                        condition_value,
+                        format!("{label}_START"),
+                    ),
+                    tacky::ast::Instruction::Label(format!("{label}_BREAK")),
+                ],
+            ]
+            .concat()
+        }
+        c::ast::Statement::For(init, condition, post, body, label, _) => {
+            let label = expect_loop_label(label);
+            let init = match *init {
+                c::ast::ForInit::Declaration(name, initializer, span) => translate_block(
+                    c::ast::Block::Declaration(name, initializer, span),
+                    temporaries,
+                ),
+                c::ast::ForInit::Expression(Some(expression)) => {
+                    translate_expression(expression, temporaries).0
+                }
+                c::ast::ForInit::Expression(None) => vec![],
+            };
+            // a missing condition is always true
+            let condition = match condition {
+                Some(condition) => {
+                    let (mut instructions, value) = translate_expression(condition, temporaries);
+                    instructions.push(tacky::ast::Instruction::JumpIfZero(
+                        value,
+                        format!("{label}_BREAK"),
+                    ));
+                    instructions
+                }
+                None => vec![],
+            };
+            let body = translate_statement(*body, temporaries);
+            let post = match post {
+                Some(post) => translate_expression(post, temporaries).0,
+                None => vec![],
+            };
             [
-                inner_instructions.as_slice(),
-                &[tacky::ast::Instruction::Return(value)],
+                init,
+                vec![tacky::ast::Instruction::Label(format!("{label}_START"))],
+                condition,
+                body,
+                vec![tacky::ast::Instruction::Label(format!("{label}_CONTINUE"))],
+                post,
+                vec![
+                    tacky::ast::Instruction::Jump(format!("{label}_START")),
+                    tacky::ast::Instruction::Label(format!("{label}_BREAK")),
+                ],
             ]
             .concat()
         }
-        _ => todo!(),
+        c::ast::Statement::Switch(control, body, labels, _) => {
+            let labels = labels.expect("label_loops labels every switch");
+            let break_label = format!("{}_BREAK", labels.label);
+            let (mut instructions, control) = translate_expression(control, temporaries);
+            instructions.extend(generate_switch_dispatch(control, labels, temporaries));
+            instructions.extend(translate_statement(*body, temporaries));
+            instructions.push(tacky::ast::Instruction::Label(break_label));
+            instructions
+        }
+        c::ast::Statement::Case(_, body, label, _) | c::ast::Statement::Default(body, label, _) => {
+            let mut instructions = vec![tacky::ast::Instruction::Label(expect_loop_label(label))];
+            instructions.extend(translate_statement(*body, temporaries));
+            instructions
+        }
+        c::ast::Statement::Null(_) => vec![],
+    }
+}
+
+fn translate_block(
+    block: c::ast::Block,
+    temporaries: &mut Temporaries,
+) -> Vec<tacky::ast::Instruction> {
+    match block {
+        c::ast::Block::Statement(statement) => translate_statement(statement, temporaries),
+        c::ast::Block::Declaration(name, Some(initializer), span) => {
+            let assignment = c::ast::Expression::Assignment(
+                Box::new(c::ast::Expression::Var(name, span)),
+                Box::new(initializer),
+                span,
+            );
+            translate_expression(assignment, temporaries).0
+        }
+        c::ast::Block::Declaration(_, None, _) => vec![],
     }
 }
 
 fn translate_function(func: c::ast::Function) -> tacky::ast::Function {
     match func {
-        c::ast::Function::Function(name, blocks) => {
-            // forcing ownership of blocks for now to get first item
-            // keeps back-compatibility while we build out local var support
-            // likely need to refactor to use refs down the line
-            let mut blocks = blocks;
-            let first_block = blocks.remove(0);
-            let statement = match first_block {
-                c::ast::Block::Statement(statement) => translate_statement(statement),
-                _ => todo!(),
-            };
-            tacky::ast::Function::Function(name, statement)
+        c::ast::Function::Function(name, blocks, _) => {
+            let mut temporaries = Temporaries::new();
+            let mut instructions: Vec<tacky::ast::Instruction> = blocks
+                .into_iter()
+                .flat_map(|block| translate_block(block, &mut temporaries))
+                .collect();
+            // falling off the end of `main` returns 0. For other functions
+            // using the result is undefined, so 0 does as well as anything
+            if !matches!(
+                instructions.last(),
+                Some(tacky::ast::Instruction::Return(_))
+            ) {
+                instructions.push(tacky::ast::Instruction::Return(
+                    tacky::ast::Value::Constant(0),
+                ));
+            }
+            tacky::ast::Function::Function(name, instructions)
         }
     }
 }
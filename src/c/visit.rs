@@ -0,0 +1,506 @@
+use super::ast::*;
+
+/*
+ * Traversals of the C AST. A pass implements one of the traits and overrides
+ * the methods for the nodes it cares about, every other node is walked with
+ * the default methods. An overriding method calls the matching `walk_*`
+ * function to keep going into the children of its node.
+ *
+ * - `Visitor` reads the tree
+ * - `VisitorMut` changes nodes in place
+ * - `Fold` consumes the tree and builds a new one
+ */
+
+// only the tests read the tree without changing it so far
+#[allow(dead_code)]
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program);
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        walk_function(self, function);
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        walk_block(self, block);
+    }
+
+    fn visit_for_init(&mut self, init: &ForInit) {
+        walk_for_init(self, init);
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression);
+    }
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    match program {
+        Program::Program(function) => visitor.visit_function(function),
+    }
+}
+
+pub fn walk_function<V: Visitor + ?Sized>(visitor: &mut V, function: &Function) {
+    match function {
+        Function::Function(_, blocks, _) => {
+            for block in blocks {
+                visitor.visit_block(block);
+            }
+        }
+    }
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, block: &Block) {
+    match block {
+        Block::Statement(statement) => visitor.visit_statement(statement),
+        Block::Declaration(_, Some(initializer), _) => visitor.visit_expression(initializer),
+        Block::Declaration(_, None, _) => {}
+    }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::Return(expression, _) | Statement::Expression(expression, _) => {
+            visitor.visit_expression(expression)
+        }
+        Statement::If(condition, then, otherwise, _) => {
+            visitor.visit_expression(condition);
+            visitor.visit_statement(then);
+            if let Some(otherwise) = otherwise {
+                visitor.visit_statement(otherwise);
+            }
+        }
+        Statement::Compound(blocks, _) => {
+            for block in blocks {
+                visitor.visit_block(block);
+            }
+        }
+        Statement::While(condition, body, _, _) => {
+            visitor.visit_expression(condition);
+            visitor.visit_statement(body);
+        }
+        Statement::DoWhile(body, condition, _, _) => {
+            visitor.visit_statement(body);
+            visitor.visit_expression(condition);
+        }
+        Statement::For(init, condition, post, body, _, _) => {
+            visitor.visit_for_init(init);
+            if let Some(condition) = condition {
+                visitor.visit_expression(condition);
+            }
+            if let Some(post) = post {
+                visitor.visit_expression(post);
+            }
+            visitor.visit_statement(body);
+        }
+        Statement::Switch(expression, body, _, _) | Statement::Case(expression, body, _, _) => {
+            visitor.visit_expression(expression);
+            visitor.visit_statement(body);
+        }
+        Statement::Default(body, _, _) => visitor.visit_statement(body),
+        Statement::Break(..) | Statement::Continue(..) | Statement::Null(_) => {}
+    }
+}
+
+pub fn walk_for_init<V: Visitor + ?Sized>(visitor: &mut V, init: &ForInit) {
+    match init {
+        ForInit::Declaration(_, Some(initializer), _) | ForInit::Expression(Some(initializer)) => {
+            visitor.visit_expression(initializer)
+        }
+        ForInit::Declaration(_, None, _) | ForInit::Expression(None) => {}
+    }
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    match expression {
+        Expression::Constant(..)
+        | Expression::CharLiteral(..)
+        | Expression::StringLiteral(..)
+        | Expression::Var(..) => {}
+        Expression::Unary(_, operand, _) => visitor.visit_expression(operand),
+        Expression::Binary(_, left, right, _) | Expression::Assignment(left, right, _) => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        }
+        Expression::Conditional(condition, then, otherwise, _) => {
+            visitor.visit_expression(condition);
+            visitor.visit_expression(then);
+            visitor.visit_expression(otherwise);
+        }
+    }
+}
+
+pub trait VisitorMut {
+    fn visit_program_mut(&mut self, program: &mut Program) {
+        walk_program_mut(self, program);
+    }
+
+    fn visit_function_mut(&mut self, function: &mut Function) {
+        walk_function_mut(self, function);
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        walk_block_mut(self, block);
+    }
+
+    fn visit_for_init_mut(&mut self, init: &mut ForInit) {
+        walk_for_init_mut(self, init);
+    }
+
+    fn visit_statement_mut(&mut self, statement: &mut Statement) {
+        walk_statement_mut(self, statement);
+    }
+
+    fn visit_expression_mut(&mut self, expression: &mut Expression) {
+        walk_expression_mut(self, expression);
+    }
+}
+
+pub fn walk_program_mut<V: VisitorMut + ?Sized>(visitor: &mut V, program: &mut Program) {
+    match program {
+        Program::Program(function) => visitor.visit_function_mut(function),
+    }
+}
+
+pub fn walk_function_mut<V: VisitorMut + ?Sized>(visitor: &mut V, function: &mut Function) {
+    match function {
+        Function::Function(_, blocks, _) => {
+            for block in blocks {
+                visitor.visit_block_mut(block);
+            }
+        }
+    }
+}
+
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(visitor: &mut V, block: &mut Block) {
+    match block {
+        Block::Statement(statement) => visitor.visit_statement_mut(statement),
+        Block::Declaration(_, Some(initializer), _) => visitor.visit_expression_mut(initializer),
+        Block::Declaration(_, None, _) => {}
+    }
+}
+
+pub fn walk_statement_mut<V: VisitorMut + ?Sized>(visitor: &mut V, statement: &mut Statement) {
+    match statement {
+        Statement::Return(expression, _) | Statement::Expression(expression, _) => {
+            visitor.visit_expression_mut(expression)
+        }
+        Statement::If(condition, then, otherwise, _) => {
+            visitor.visit_expression_mut(condition);
+            visitor.visit_statement_mut(then);
+            if let Some(otherwise) = otherwise {
+                visitor.visit_statement_mut(otherwise);
+            }
+        }
+        Statement::Compound(blocks, _) => {
+            for block in blocks {
+                visitor.visit_block_mut(block);
+            }
+        }
+        Statement::While(condition, body, _, _) => {
+            visitor.visit_expression_mut(condition);
+            visitor.visit_statement_mut(body);
+        }
+        Statement::DoWhile(body, condition, _, _) => {
+            visitor.visit_statement_mut(body);
+            visitor.visit_expression_mut(condition);
+        }
+        Statement::For(init, condition, post, body, _, _) => {
+            visitor.visit_for_init_mut(init);
+            if let Some(condition) = condition {
+                visitor.visit_expression_mut(condition);
+            }
+            if let Some(post) = post {
+                visitor.visit_expression_mut(post);
+            }
+            visitor.visit_statement_mut(body);
+        }
+        Statement::Switch(expression, body, _, _) | Statement::Case(expression, body, _, _) => {
+            visitor.visit_expression_mut(expression);
+            visitor.visit_statement_mut(body);
+        }
+        Statement::Default(body, _, _) => visitor.visit_statement_mut(body),
+        Statement::Break(..) | Statement::Continue(..) | Statement::Null(_) => {}
+    }
+}
+
+pub fn walk_for_init_mut<V: VisitorMut + ?Sized>(visitor: &mut V, init: &mut ForInit) {
+    match init {
+        ForInit::Declaration(_, Some(initializer), _) | ForInit::Expression(Some(initializer)) => {
+            visitor.visit_expression_mut(initializer)
+        }
+        ForInit::Declaration(_, None, _) | ForInit::Expression(None) => {}
+    }
+}
+
+pub fn walk_expression_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expression: &mut Expression) {
+    match expression {
+        Expression::Constant(..)
+        | Expression::CharLiteral(..)
+        | Expression::StringLiteral(..)
+        | Expression::Var(..) => {}
+        Expression::Unary(_, operand, _) => visitor.visit_expression_mut(operand),
+        Expression::Binary(_, left, right, _) | Expression::Assignment(left, right, _) => {
+            visitor.visit_expression_mut(left);
+            visitor.visit_expression_mut(right);
+        }
+        Expression::Conditional(condition, then, otherwise, _) => {
+            visitor.visit_expression_mut(condition);
+            visitor.visit_expression_mut(then);
+            visitor.visit_expression_mut(otherwise);
+        }
+    }
+}
+
+// no pass rebuilds the tree yet, only the tests do
+#[allow(dead_code)]
+pub trait Fold {
+    fn fold_program(&mut self, program: Program) -> Program {
+        walk_program_fold(self, program)
+    }
+
+    fn fold_function(&mut self, function: Function) -> Function {
+        walk_function_fold(self, function)
+    }
+
+    fn fold_block(&mut self, block: Block) -> Block {
+        walk_block_fold(self, block)
+    }
+
+    fn fold_for_init(&mut self, init: ForInit) -> ForInit {
+        walk_for_init_fold(self, init)
+    }
+
+    fn fold_statement(&mut self, statement: Statement) -> Statement {
+        walk_statement_fold(self, statement)
+    }
+
+    fn fold_expression(&mut self, expression: Expression) -> Expression {
+        walk_expression_fold(self, expression)
+    }
+}
+
+pub fn walk_program_fold<F: Fold + ?Sized>(folder: &mut F, program: Program) -> Program {
+    match program {
+        Program::Program(function) => Program::Program(folder.fold_function(function)),
+    }
+}
+
+pub fn walk_function_fold<F: Fold + ?Sized>(folder: &mut F, function: Function) -> Function {
+    match function {
+        Function::Function(name, blocks, span) => Function::Function(
+            name,
+            blocks
+                .into_iter()
+                .map(|block| folder.fold_block(block))
+                .collect(),
+            span,
+        ),
+    }
+}
+
+pub fn walk_block_fold<F: Fold + ?Sized>(folder: &mut F, block: Block) -> Block {
+    match block {
+        Block::Statement(statement) => Block::Statement(folder.fold_statement(statement)),
+        Block::Declaration(name, initializer, span) => Block::Declaration(
+            name,
+            initializer.map(|initializer| folder.fold_expression(initializer)),
+            span,
+        ),
+    }
+}
+
+pub fn walk_statement_fold<F: Fold + ?Sized>(folder: &mut F, statement: Statement) -> Statement {
+    match statement {
+        Statement::Return(expression, span) => {
+            Statement::Return(folder.fold_expression(expression), span)
+        }
+        Statement::Expression(expression, span) => {
+            Statement::Expression(folder.fold_expression(expression), span)
+        }
+        Statement::If(condition, then, otherwise, span) => Statement::If(
+            folder.fold_expression(condition),
+            Box::new(folder.fold_statement(*then)),
+            otherwise.map(|otherwise| Box::new(folder.fold_statement(*otherwise))),
+            span,
+        ),
+        Statement::Compound(blocks, span) => Statement::Compound(
+            blocks
+                .into_iter()
+                .map(|block| folder.fold_block(block))
+                .collect(),
+            span,
+        ),
+        Statement::Break(label, span) => Statement::Break(label, span),
+        Statement::Continue(label, span) => Statement::Continue(label, span),
+        Statement::While(condition, body, label, span) => Statement::While(
+            folder.fold_expression(condition),
+            Box::new(folder.fold_statement(*body)),
+            label,
+            span,
+        ),
+        Statement::DoWhile(body, condition, label, span) => Statement::DoWhile(
+            Box::new(folder.fold_statement(*body)),
+            folder.fold_expression(condition),
+            label,
+            span,
+        ),
+        Statement::For(init, condition, post, body, label, span) => Statement::For(
+            Box::new(folder.fold_for_init(*init)),
+            condition.map(|condition| folder.fold_expression(condition)),
+            post.map(|post| folder.fold_expression(post)),
+            Box::new(folder.fold_statement(*body)),
+            label,
+            span,
+        ),
+        Statement::Switch(control, body, labels, span) => Statement::Switch(
+            folder.fold_expression(control),
+            Box::new(folder.fold_statement(*body)),
+            labels,
+            span,
+        ),
+        Statement::Case(value, body, label, span) => Statement::Case(
+            folder.fold_expression(value),
+            Box::new(folder.fold_statement(*body)),
+            label,
+            span,
+        ),
+        Statement::Default(body, label, span) => {
+            Statement::Default(Box::new(folder.fold_statement(*body)), label, span)
+        }
+        Statement::Null(span) => Statement::Null(span),
+    }
+}
+
+pub fn walk_for_init_fold<F: Fold + ?Sized>(folder: &mut F, init: ForInit) -> ForInit {
+    match init {
+        ForInit::Declaration(name, initializer, span) => ForInit::Declaration(
+            name,
+            initializer.map(|initializer| folder.fold_expression(initializer)),
+            span,
+        ),
+        ForInit::Expression(expression) => {
+            ForInit::Expression(expression.map(|expression| folder.fold_expression(expression)))
+        }
+    }
+}
+
+pub fn walk_expression_fold<F: Fold + ?Sized>(
+    folder: &mut F,
+    expression: Expression,
+) -> Expression {
+    match expression {
+        Expression::Unary(operator, operand, span) => {
+            Expression::Unary(operator, Box::new(folder.fold_expression(*operand)), span)
+        }
+        Expression::Binary(operator, left, right, span) => Expression::Binary(
+            operator,
+            Box::new(folder.fold_expression(*left)),
+            Box::new(folder.fold_expression(*right)),
+            span,
+        ),
+        Expression::Assignment(left, right, span) => Expression::Assignment(
+            Box::new(folder.fold_expression(*left)),
+            Box::new(folder.fold_expression(*right)),
+            span,
+        ),
+        Expression::Conditional(condition, then, otherwise, span) => Expression::Conditional(
+            Box::new(folder.fold_expression(*condition)),
+            Box::new(folder.fold_expression(*then)),
+            Box::new(folder.fold_expression(*otherwise)),
+            span,
+        ),
+        leaf @ (Expression::Constant(..)
+        | Expression::CharLiteral(..)
+        | Expression::StringLiteral(..)
+        | Expression::Var(..)) => leaf,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::c::from_lexical::parse_program;
+    use crate::c::lexer::lex_contents;
+    use crate::core::parser::Parser;
+
+    fn parse(src: &str) -> Program {
+        parse_program(&mut Parser::new(lex_contents(src.to_string()).unwrap())).unwrap()
+    }
+
+    #[derive(Default)]
+    struct VariableUses(Vec<String>);
+
+    impl Visitor for VariableUses {
+        fn visit_expression(&mut self, expression: &Expression) {
+            if let Expression::Var(name, _) = expression {
+                self.0.push(name.clone());
+            }
+            walk_expression(self, expression);
+        }
+    }
+
+    #[test]
+    fn visitor_reaches_every_expression() {
+        let program = parse("int main(void) { int a = b; c = -(d + e); ; return a; }");
+        let mut uses = VariableUses::default();
+        uses.visit_program(&program);
+        assert_eq!(uses.0, vec!["b", "c", "d", "e", "a"]);
+    }
+
+    struct Rename;
+
+    impl VisitorMut for Rename {
+        fn visit_expression_mut(&mut self, expression: &mut Expression) {
+            if let Expression::Var(name, _) = expression {
+                name.push_str(".0");
+            }
+            walk_expression_mut(self, expression);
+        }
+    }
+
+    #[test]
+    fn visitor_mut_changes_nodes_in_place() {
+        let mut program = parse("int main(void) { int a = 1; return a * a; }");
+        Rename.visit_program_mut(&mut program);
+        let mut uses = VariableUses::default();
+        uses.visit_program(&program);
+        assert_eq!(uses.0, vec!["a.0", "a.0"]);
+    }
+
+    // folds `-constant` into a single constant, bottom up
+    struct FoldNegation;
+
+    impl Fold for FoldNegation {
+        fn fold_expression(&mut self, expression: Expression) -> Expression {
+            match walk_expression_fold(self, expression) {
+                Expression::Unary(UnaryOperator::Negation, operand, span) => match *operand {
+                    Expression::Constant(value, _) => Expression::Constant(-value, span),
+                    operand => Expression::Unary(UnaryOperator::Negation, Box::new(operand), span),
+                },
+                expression => expression,
+            }
+        }
+    }
+
+    #[test]
+    fn fold_rebuilds_tree() {
+        let program = FoldNegation.fold_program(parse("int main(void) { return -(-2) + -a; }"));
+        let Program::Program(Function::Function(_, blocks, _)) = program;
+        let Block::Statement(Statement::Return(Expression::Binary(_, left, right, _), _)) =
+            &blocks[0]
+        else {
+            panic!("expected a return of a binary expression");
+        };
+        assert!(matches!(left.as_ref(), Expression::Constant(2, _)));
+        assert!(matches!(
+            right.as_ref(),
+            Expression::Unary(UnaryOperator::Negation, _, _)
+        ));
+    }
+}
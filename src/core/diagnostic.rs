@@ -0,0 +1,85 @@
+use super::source_map::SourceMap;
+use super::span::Span;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/**
+ * A problem found in the program being compiled. Rendered for the user as
+ * `file:line:col: error: message`, followed by one line per note
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+    pub notes: Vec<Diagnostic>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>, span: Span) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message: message.into(),
+            span,
+            notes: vec![],
+        }
+    }
+
+    /**
+     * Attaches extra information pointing at another location, e.g. the
+     * opening parenthesis an expected `)` should have matched
+     */
+    pub fn with_note(mut self, message: impl Into<String>, span: Span) -> Self {
+        self.notes.push(Diagnostic {
+            severity: Severity::Note,
+            message: message.into(),
+            span,
+            notes: vec![],
+        });
+        self
+    }
+
+    pub fn render(&self, source_map: &SourceMap) -> String {
+        let mut rendered = format!(
+            "{}: {}: {}",
+            source_map.location(self.span),
+            self.severity,
+            self.message
+        );
+        for note in &self.notes {
+            rendered.push('\n');
+            rendered.push_str(&note.render(source_map));
+        }
+        rendered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_with_notes() {
+        let source_map = SourceMap::new("main.c");
+        let diagnostic = Diagnostic::error("expected ')'", Span::new(10, 2, 9, 1))
+            .with_note("to match this '('", Span::new(4, 2, 3, 1));
+        assert_eq!(
+            diagnostic.render(&source_map),
+            "main.c:2:9: error: expected ')'\nmain.c:2:3: note: to match this '('"
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use super::source_map::SourceMap;
+use super::span::Span;
+use serde::Serialize;
+use serde::ser::{self, SerializeSeq, SerializeStruct, SerializeStructVariant};
+
+// lists longer than this are split over several lines
+const LINE_WIDTH: usize = 80;
+const INDENT: &str = "  ";
+
+/**
+ * The form every syntax tree is dumped in. The trees themselves use
+ * positional variants, which gain fields as the compiler grows, so each node
+ * is given named fields here to keep the dump stable for its consumers
+ */
+#[derive(Debug, PartialEq)]
+pub enum Node {
+    // `{"While": {"condition": ..., "body": ...}}`
+    Variant(&'static str, Vec<(&'static str, Node)>),
+    // a variant without fields, like an operator
+    Unit(&'static str),
+    // a value that is not one of several kinds, like a span
+    Struct(&'static str, Vec<(&'static str, Node)>),
+    List(Vec<Node>),
+    String(String),
+    Integer(i64),
+    Null,
+}
+
+/**
+ * Implemented by every syntax tree that can be dumped. Spans name their file,
+ * which needs the source map
+ */
+pub trait Dump {
+    fn dump(&self, source_map: &SourceMap) -> Node;
+}
+
+impl<T: Dump + ?Sized> Dump for Box<T> {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        self.as_ref().dump(source_map)
+    }
+}
+
+impl<T: Dump> Dump for Option<T> {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        match self {
+            Some(value) => value.dump(source_map),
+            None => Node::Null,
+        }
+    }
+}
+
+impl<T: Dump> Dump for Vec<T> {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        Node::List(self.iter().map(|item| item.dump(source_map)).collect())
+    }
+}
+
+impl Dump for String {
+    fn dump(&self, _source_map: &SourceMap) -> Node {
+        Node::String(self.clone())
+    }
+}
+
+impl Dump for i32 {
+    fn dump(&self, _source_map: &SourceMap) -> Node {
+        Node::Integer(*self as i64)
+    }
+}
+
+impl Dump for u8 {
+    fn dump(&self, _source_map: &SourceMap) -> Node {
+        Node::Integer(*self as i64)
+    }
+}
+
+impl Dump for Span {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        Node::Struct(
+            "Span",
+            vec![
+                ("file", Node::String(source_map.name(self.file).to_string())),
+                ("offset", Node::Integer(self.offset as i64)),
+                ("line", Node::Integer(self.line as i64)),
+                ("column", Node::Integer(self.column as i64)),
+                ("length", Node::Integer(self.length as i64)),
+            ],
+        )
+    }
+}
+
+impl Serialize for Node {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Node::Variant(name, fields) => {
+                let mut variant =
+                    serializer.serialize_struct_variant("Node", 0, name, fields.len())?;
+                for (key, value) in fields {
+                    variant.serialize_field(key, value)?;
+                }
+                variant.end()
+            }
+            Node::Unit(name) => serializer.serialize_unit_variant("Node", 0, name),
+            Node::Struct(name, fields) => {
+                let mut structure = serializer.serialize_struct(name, fields.len())?;
+                for (key, value) in fields {
+                    structure.serialize_field(key, value)?;
+                }
+                structure.end()
+            }
+            Node::List(items) => {
+                let mut list = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    list.serialize_element(item)?;
+                }
+                list.end()
+            }
+            Node::String(value) => serializer.serialize_str(value),
+            Node::Integer(value) => serializer.serialize_i64(*value),
+            Node::Null => serializer.serialize_none(),
+        }
+    }
+}
+
+pub fn to_json(tree: &impl Dump, source_map: &SourceMap) -> String {
+    serde_json::to_string_pretty(&tree.dump(source_map)).expect("syntax trees always serialize")
+}
+
+/**
+ * Renders a tree as an S-expression. Variants and structs become lists headed
+ * by their name, `(Binary (operator Add) (left ...) ...)`, fields are
+ * `(name value)` pairs and unit variants are bare atoms
+ */
+pub fn to_sexpr(tree: &impl Dump, source_map: &SourceMap) -> String {
+    node_to_sexpr(&tree.dump(source_map))
+}
+
+fn node_to_sexpr(node: &Node) -> String {
+    render(&sexpr(node), 0)
+}
+
+enum Sexpr {
+    Atom(String),
+    List(Vec<Sexpr>),
+}
+
+fn sexpr(node: &Node) -> Sexpr {
+    match node {
+        Node::Variant(name, fields) | Node::Struct(name, fields) => Sexpr::List(
+            std::iter::once(Sexpr::Atom(name.to_string()))
+                .chain(fields.iter().map(|(key, value)| {
+                    Sexpr::List(vec![Sexpr::Atom(key.to_string()), sexpr(value)])
+                }))
+                .collect(),
+        ),
+        Node::Unit(name) => Sexpr::Atom(name.to_string()),
+        Node::List(items) => Sexpr::List(items.iter().map(sexpr).collect()),
+        // strings are quoted with JSON escapes so names cannot look like atoms
+        Node::String(value) => {
+            Sexpr::Atom(serde_json::to_string(value).expect("strings always serialize"))
+        }
+        Node::Integer(value) => Sexpr::Atom(value.to_string()),
+        Node::Null => Sexpr::Atom(String::from("nil")),
+    }
+}
+
+fn render_flat(sexpr: &Sexpr) -> String {
+    match sexpr {
+        Sexpr::Atom(atom) => atom.clone(),
+        Sexpr::List(items) => {
+            let items: Vec<String> = items.iter().map(render_flat).collect();
+            format!("({})", items.join(" "))
+        }
+    }
+}
+
+fn render(sexpr: &Sexpr, depth: usize) -> String {
+    let flat = render_flat(sexpr);
+    let Sexpr::List(items) = sexpr else {
+        return flat;
+    };
+    if depth * INDENT.len() + flat.len() <= LINE_WIDTH || items.is_empty() {
+        return flat;
+    }
+    // atoms at the start stay on the first line, like `(Binary Add`
+    let atoms = items
+        .iter()
+        .take_while(|item| matches!(item, Sexpr::Atom(_)))
+        .count();
+    let (head, rest) = match atoms {
+        0 => (render(&items[0], depth + 1), &items[1..]),
+        _ => {
+            let atoms: Vec<String> = items[..atoms].iter().map(render_flat).collect();
+            (atoms.join(" "), &items[atoms.len()..])
+        }
+    };
+    let indent = INDENT.repeat(depth + 1);
+    let rest: String = rest
+        .iter()
+        .map(|item| format!("\n{indent}{}", render(item, depth + 1)))
+        .collect();
+    format!("({head}{rest})")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> Node {
+        Node::Variant(
+            "Node",
+            vec![
+                (
+                    "name",
+                    Node::Variant(
+                        "Named",
+                        vec![
+                            ("name", Node::String(String::from("a\"b"))),
+                            ("value", Node::Null),
+                        ],
+                    ),
+                ),
+                (
+                    "children",
+                    Node::List(vec![Node::Unit("Leaf"), Node::Integer(-1)]),
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn sexpr_uses_variant_and_field_names() {
+        assert_eq!(
+            node_to_sexpr(&tree()),
+            "(Node (name (Named (name \"a\\\"b\") (value nil))) (children (Leaf -1)))"
+        );
+        assert_eq!(node_to_sexpr(&Node::Unit("Leaf")), "Leaf");
+    }
+
+    #[test]
+    fn json_uses_variant_and_field_names() {
+        let json: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&tree()).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "Node": {
+                    "name": {"Named": {"name": "a\"b", "value": null}},
+                    "children": ["Leaf", -1]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn spans_name_their_file() {
+        let mut source_map = SourceMap::new("main.c");
+        let mut span = Span::new(4, 1, 5, 2);
+        span.file = source_map.add("foo.h");
+        assert_eq!(
+            to_sexpr(&span, &source_map),
+            "(Span (file \"foo.h\") (offset 4) (line 1) (column 5) (length 2))"
+        );
+    }
+}
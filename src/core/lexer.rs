@@ -0,0 +1,525 @@
+use super::diagnostic::Diagnostic;
+use super::span::{Span, Spanned};
+use std::collections::HashMap;
+use std::fmt;
+
+/**
+ * A malformed token. The lexer keeps going after one, so every error in a
+ * file is reported in a single run
+ */
+#[derive(Debug, PartialEq, Clone)]
+pub struct LexError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl LexError {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        LexError {
+            message: message.into(),
+            span,
+        }
+    }
+}
+
+impl From<LexError> for Diagnostic {
+    fn from(error: LexError) -> Self {
+        Diagnostic::error(error.message, error.span)
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.span)
+    }
+}
+
+// length of the token a rule recognizes at the start of the remaining input,
+// given the tokens lexed so far
+type Scan<T> = Box<dyn Fn(&[Spanned<T>], &str) -> Option<usize>>;
+// turns the text a rule recognized into a token, or an error message
+type Classify<T> = Box<dyn Fn(&str) -> Result<T, String>>;
+// turns text that is not a token and the reason why into a token
+type Invalid<T> = Box<dyn Fn(&str, String) -> T>;
+
+struct Rule<T> {
+    scan: Scan<T>,
+    classify: Classify<T>,
+}
+
+/**
+ * Table-driven lexer shared by the languages the compiler reads. A language
+ * describes its tokens with keywords, punctuators and rules for everything
+ * else (identifiers, literals...), then calls `lex` on each input.
+ *
+ * At every position the rules are tried in the order they were added. Text
+ * matched by a rule becomes a keyword if it is spelled like one, otherwise
+ * the rule classifies it. When no rule applies the longest punctuator wins,
+ * and any other character is an error
+ */
+pub struct Lexer<T> {
+    keywords: HashMap<&'static str, T>,
+    punctuators: Vec<(&'static str, T)>,
+    rules: Vec<Rule<T>>,
+    line_comment: Option<&'static str>,
+    block_comment: Option<(&'static str, &'static str)>,
+    newline: Option<T>,
+    invalid: Option<Invalid<T>>,
+    splice_lines: bool,
+}
+
+impl<T: Clone> Lexer<T> {
+    pub fn new() -> Self {
+        Lexer {
+            keywords: HashMap::new(),
+            punctuators: vec![],
+            rules: vec![],
+            line_comment: None,
+            block_comment: None,
+            newline: None,
+            invalid: None,
+            splice_lines: false,
+        }
+    }
+
+    pub fn keywords(mut self, keywords: impl IntoIterator<Item = (&'static str, T)>) -> Self {
+        self.keywords.extend(keywords);
+        self
+    }
+
+    /**
+     * Adds punctuators, several spellings may map to the same token
+     */
+    pub fn punctuators(mut self, punctuators: impl IntoIterator<Item = (&'static str, T)>) -> Self {
+        self.punctuators.extend(punctuators);
+        // longest first for maximal munch
+        self.punctuators
+            .sort_by_key(|(spelling, _)| std::cmp::Reverse(spelling.len()));
+        self
+    }
+
+    /**
+     * Adds a rule for tokens that are not spelled out in advance. `scan`
+     * returns the length of the token at the start of its input, if any
+     */
+    pub fn rule(
+        self,
+        scan: impl Fn(&str) -> Option<usize> + 'static,
+        classify: impl Fn(&str) -> Result<T, String> + 'static,
+    ) -> Self {
+        self.contextual_rule(move |_, rest| scan(rest), classify)
+    }
+
+    /**
+     * Like `rule`, but `scan` also sees the tokens lexed so far, for tokens
+     * that only exist in some places
+     */
+    pub fn contextual_rule(
+        mut self,
+        scan: impl Fn(&[Spanned<T>], &str) -> Option<usize> + 'static,
+        classify: impl Fn(&str) -> Result<T, String> + 'static,
+    ) -> Self {
+        self.rules.push(Rule {
+            scan: Box::new(scan),
+            classify: Box::new(classify),
+        });
+        self
+    }
+
+    /**
+     * Comments are skipped like whitespace, a line comment runs until the
+     * end of the line
+     */
+    pub fn line_comment(mut self, start: &'static str) -> Self {
+        self.line_comment = Some(start);
+        self
+    }
+
+    pub fn block_comment(mut self, start: &'static str, end: &'static str) -> Self {
+        self.block_comment = Some((start, end));
+        self
+    }
+
+    /**
+     * Emits `token` at every line end instead of treating it as whitespace
+     */
+    pub fn newline_token(mut self, token: T) -> Self {
+        self.newline = Some(token);
+        self
+    }
+
+    /**
+     * Emits `token(text, message)` for text that is not a token instead of
+     * reporting an error, so that a later stage can decide whether it matters
+     */
+    pub fn invalid_token(mut self, token: impl Fn(&str, String) -> T + 'static) -> Self {
+        self.invalid = Some(Box::new(token));
+        self
+    }
+
+    /**
+     * Joins lines ending in a backslash before lexing. Spans still point at
+     * the original input
+     */
+    pub fn splice_lines(mut self) -> Self {
+        self.splice_lines = true;
+        self
+    }
+
+    pub fn lex(&self, src: &str) -> Result<Vec<Spanned<T>>, Vec<LexError>> {
+        Scanner::new(self, src).lex()
+    }
+}
+
+/**
+ * Removes every backslash-newline pair so that spliced lines read as one.
+ * Returns the spliced text along with where each splice happened, as
+ * (offset in spliced text, bytes removed)
+ */
+fn splice_lines(src: &str) -> (String, Vec<(usize, usize)>) {
+    let mut spliced = String::with_capacity(src.len());
+    let mut splices = vec![];
+    let mut rest = src;
+    while let Some(index) = rest.find('\\') {
+        let after = &rest[index + 1..];
+        let removed = if after.starts_with('\n') {
+            2
+        } else if after.starts_with("\r\n") {
+            3
+        } else {
+            spliced.push_str(&rest[..index + 1]);
+            rest = after;
+            continue;
+        };
+        spliced.push_str(&rest[..index]);
+        splices.push((spliced.len(), removed));
+        rest = &rest[index + removed..];
+    }
+    spliced.push_str(rest);
+    (spliced, splices)
+}
+
+/**
+ * Single pass over one input. Walks the source once with a byte cursor,
+ * keeping track of the current line and column so every token gets a span
+ * without having to rescan the input.
+ * The cursor moves over the spliced text, spans are mapped back to offsets in
+ * the original input
+ */
+struct Scanner<'a, T> {
+    lexer: &'a Lexer<T>,
+    src: String,
+    splices: Vec<(usize, usize)>,
+    next_splice: usize,
+    spliced_bytes: usize,
+    pos: usize,
+    line: usize,
+    column: usize,
+    errors: Vec<LexError>,
+}
+
+impl<'a, T: Clone> Scanner<'a, T> {
+    fn new(lexer: &'a Lexer<T>, src: &str) -> Self {
+        let (src, splices) = match lexer.splice_lines {
+            true => splice_lines(src),
+            false => (src.to_string(), vec![]),
+        };
+        Scanner {
+            lexer,
+            src,
+            splices,
+            next_splice: 0,
+            spliced_bytes: 0,
+            pos: 0,
+            line: 1,
+            column: 1,
+            errors: vec![],
+        }
+    }
+
+    /**
+     * Accounts for the splices removed right before the cursor, each of them
+     * was a newline in the original input
+     */
+    fn apply_splices(&mut self) {
+        while let Some(&(offset, removed)) = self.splices.get(self.next_splice) {
+            if offset != self.pos {
+                break;
+            }
+            self.line += 1;
+            self.column = 1;
+            self.spliced_bytes += removed;
+            self.next_splice += 1;
+        }
+    }
+
+    /**
+     * Offset of the cursor in the original input
+     */
+    fn original_offset(&self) -> usize {
+        self.pos + self.spliced_bytes
+    }
+
+    /**
+     * Moves the cursor `len` bytes forward, updating line and column
+     */
+    fn advance(&mut self, len: usize) {
+        let end = self.pos + len;
+        while self.pos < end {
+            self.apply_splices();
+            if self.src.as_bytes()[self.pos] == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            self.apply_splices();
+            let rest = &self.src[self.pos..];
+            if let Some(start) = self.lexer.line_comment
+                && rest.starts_with(start)
+            {
+                let length = rest.find('\n').unwrap_or(rest.len());
+                self.advance(length);
+            } else if let Some((start, end)) = self.lexer.block_comment
+                && let Some(comment) = rest.strip_prefix(start)
+            {
+                let Some(length) = comment.find(end) else {
+                    self.errors.push(LexError {
+                        message: String::from("unterminated comment"),
+                        span: Span::new(
+                            self.original_offset(),
+                            self.line,
+                            self.column,
+                            start.len(),
+                        ),
+                    });
+                    self.advance(rest.len());
+                    return;
+                };
+                self.advance(start.len() + length + end.len());
+            } else if rest.starts_with('\n') && self.lexer.newline.is_some() {
+                return;
+            } else if rest.starts_with(|c: char| c.is_ascii_whitespace()) {
+                self.advance(1);
+            } else {
+                return;
+            }
+        }
+    }
+
+    /**
+     * Length of the token under the cursor and the rule that recognized it.
+     * Without a rule the token is a punctuator, a newline or a single
+     * unknown character
+     */
+    fn token_length(&self, tokens: &[Spanned<T>]) -> (usize, Option<&'a Rule<T>>) {
+        let rest = &self.src[self.pos..];
+        if rest.starts_with('\n') {
+            return (1, None);
+        }
+        let lexer = self.lexer;
+        for rule in &lexer.rules {
+            if let Some(length) = (rule.scan)(tokens, rest).filter(|length| *length > 0) {
+                return (length, Some(rule));
+            }
+        }
+        if let Some((spelling, _)) = lexer
+            .punctuators
+            .iter()
+            .find(|(spelling, _)| rest.starts_with(spelling))
+        {
+            return (spelling.len(), None);
+        }
+        (rest.chars().next().map_or(1, char::len_utf8), None)
+    }
+
+    fn classify(&self, content: &str, rule: Option<&Rule<T>>) -> Result<T, String> {
+        let lexer = self.lexer;
+        if let Some(keyword) = lexer.keywords.get(content) {
+            return Ok(keyword.clone());
+        }
+        let token = match rule {
+            Some(rule) => return (rule.classify)(content),
+            None if content == "\n" => lexer.newline.clone(),
+            None => lexer
+                .punctuators
+                .iter()
+                .find(|(spelling, _)| *spelling == content)
+                .map(|(_, token)| token.clone()),
+        };
+        token.ok_or(format!("stray '{content}' in program"))
+    }
+
+    fn lex(mut self) -> Result<Vec<Spanned<T>>, Vec<LexError>> {
+        let mut tokens = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.pos >= self.src.len() {
+                return match self.errors.is_empty() {
+                    true => Ok(tokens),
+                    false => Err(self.errors),
+                };
+            }
+            let (length, rule) = self.token_length(&tokens);
+            let (start_offset, line, column) = (self.original_offset(), self.line, self.column);
+            let content = self.src[self.pos..self.pos + length].to_string();
+            self.advance(length);
+            let span = Span::new(
+                start_offset,
+                line,
+                column,
+                self.original_offset() - start_offset,
+            );
+            match (self.classify(&content, rule), &self.lexer.invalid) {
+                (Ok(token), _) => tokens.push(Spanned::new(token, span)),
+                (Err(message), Some(invalid)) => {
+                    tokens.push(Spanned::new(invalid(&content, message), span))
+                }
+                (Err(message), None) => self.errors.push(LexError { message, span }),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a small assembly-like language, unrelated to C
+    #[derive(Debug, Clone, PartialEq)]
+    enum Tok {
+        Mov,
+        Ret,
+        Register(String),
+        Immediate(i64),
+        Label(String),
+        Comma,
+        Colon,
+        Newline,
+        Invalid(String, String),
+    }
+
+    fn asm_lexer() -> Lexer<Tok> {
+        Lexer::new()
+            .keywords([("movl", Tok::Mov), ("ret", Tok::Ret)])
+            .punctuators([(",", Tok::Comma), (":", Tok::Colon)])
+            .rule(
+                |rest| {
+                    rest.strip_prefix('%')
+                        .map(|name| 1 + name.bytes().take_while(u8::is_ascii_alphanumeric).count())
+                },
+                |content| Ok(Tok::Register(content[1..].to_string())),
+            )
+            .rule(
+                |rest| {
+                    rest.strip_prefix('$').map(|value| {
+                        1 + value
+                            .bytes()
+                            .take_while(|b| b.is_ascii_digit() || *b == b'-')
+                            .count()
+                    })
+                },
+                |content| {
+                    content[1..]
+                        .parse()
+                        .map(Tok::Immediate)
+                        .map_err(|_| format!("bad immediate {content}"))
+                },
+            )
+            .rule(
+                |rest| Some(rest.bytes().take_while(u8::is_ascii_alphabetic).count()),
+                |content| Ok(Tok::Label(content.to_string())),
+            )
+            .line_comment("#")
+            .newline_token(Tok::Newline)
+    }
+
+    fn kinds(lexer: &Lexer<Tok>, src: &str) -> Vec<Tok> {
+        lexer
+            .lex(src)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.node)
+            .collect()
+    }
+
+    #[test]
+    fn keywords_win_over_rules() {
+        assert_eq!(
+            kinds(&asm_lexer(), "main:\n  movl $-2, %eax # result\n  ret"),
+            vec![
+                Tok::Label(String::from("main")),
+                Tok::Colon,
+                Tok::Newline,
+                Tok::Mov,
+                Tok::Immediate(-2),
+                Tok::Comma,
+                Tok::Register(String::from("eax")),
+                Tok::Newline,
+                Tok::Ret,
+            ]
+        );
+    }
+
+    #[test]
+    fn collects_errors_with_spans() {
+        let errors = asm_lexer().lex("movl $1-, %eax\n  ret ;").unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                LexError {
+                    message: String::from("bad immediate $1-"),
+                    span: Span::new(5, 1, 6, 3),
+                },
+                LexError {
+                    message: String::from("stray ';' in program"),
+                    span: Span::new(21, 2, 7, 1),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_text_can_become_tokens() {
+        let lexer =
+            asm_lexer().invalid_token(|text, message| Tok::Invalid(text.to_string(), message));
+        assert_eq!(
+            kinds(&lexer, "movl $1-, ;"),
+            vec![
+                Tok::Mov,
+                Tok::Invalid(String::from("$1-"), String::from("bad immediate $1-")),
+                Tok::Comma,
+                Tok::Invalid(String::from(";"), String::from("stray ';' in program")),
+            ]
+        );
+    }
+
+    #[test]
+    fn punctuators_use_maximal_munch() {
+        let lexer = Lexer::new()
+            .punctuators([(":", Tok::Colon), ("::", Tok::Comma)])
+            .block_comment("(*", "*)");
+        assert_eq!(
+            kinds(&lexer, ":::(* skipped *):"),
+            vec![Tok::Comma, Tok::Colon, Tok::Colon]
+        );
+        assert_eq!(
+            lexer.lex(": (* open").unwrap_err()[0].to_string(),
+            "unterminated comment at 1:3"
+        );
+    }
+
+    #[test]
+    fn spliced_lines_keep_original_spans() {
+        let lexer = asm_lexer().splice_lines();
+        let tokens = lexer.lex("re\\\nt\nret").unwrap();
+        assert_eq!(tokens[0], Spanned::new(Tok::Ret, Span::new(0, 1, 1, 5)));
+        assert_eq!(tokens[2], Spanned::new(Tok::Ret, Span::new(6, 3, 1, 3)));
+    }
+}
@@ -1 +1,7 @@
+pub mod diagnostic;
+#[cfg(feature = "dump-ast")]
+pub mod dump;
+pub mod lexer;
 pub mod parser;
+pub mod source_map;
+pub mod span;
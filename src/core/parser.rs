@@ -1,10 +1,29 @@
+use super::span::{Span, Spanned};
+
+/**
+ * Returned by `Parser::expect` when the next token is not the one the
+ * grammar requires. `found` is None at the end of the input
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpectError<T> {
+    pub expected: String,
+    pub found: Option<T>,
+    pub span: Span,
+}
+
+/**
+ * A saved parser position, see `Parser::checkpoint`
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Checkpoint(usize);
+
 pub struct Parser<T> {
-    tokens: Vec<T>,
+    tokens: Vec<Spanned<T>>,
     cursor: usize,
 }
 
 impl<T: Clone> Parser<T> {
-    pub fn new(tokens: Vec<T>) -> Self {
+    pub fn new(tokens: Vec<Spanned<T>>) -> Self {
         Parser { tokens, cursor: 0 }
     }
 
@@ -15,7 +34,7 @@ impl<T: Clone> Parser<T> {
         match self.tokens.get(self.cursor) {
             Some(token) => {
                 self.cursor += 1;
-                Some(token.clone())
+                Some(token.node.clone())
             }
             None => None,
         }
@@ -25,19 +44,119 @@ impl<T: Clone> Parser<T> {
      * Return a reference to the next lexical token's value without consuming
      */
     pub fn peek(&self) -> Option<&T> {
-        self.tokens.get(self.cursor)
+        self.tokens.get(self.cursor).map(|token| &token.node)
+    }
+
+    /**
+     * Consumes the next token if `pred` accepts it. Otherwise nothing is
+     * consumed and the error says that `what` was expected here
+     */
+    pub fn expect(&mut self, pred: impl Fn(&T) -> bool, what: &str) -> Result<T, ExpectError<T>> {
+        match self.peek() {
+            Some(token) if pred(token) => Ok(self.eat().expect("token was just peeked")),
+            found => Err(ExpectError {
+                expected: what.to_string(),
+                found: found.cloned(),
+                span: self.peek_span(),
+            }),
+        }
+    }
+
+    /**
+     * Span of the next lexical token. Falls back to the end of the last token
+     * so that "unexpected end of input" errors still have a location
+     */
+    pub fn peek_span(&self) -> Span {
+        match self.tokens.get(self.cursor) {
+            Some(token) => token.span,
+            None => self.end_of_input_span(),
+        }
+    }
+
+    /**
+     * Span of the most recently consumed token
+     */
+    pub fn last_span(&self) -> Span {
+        match self.cursor.checked_sub(1).and_then(|i| self.tokens.get(i)) {
+            Some(token) => token.span,
+            None => self.peek_span(),
+        }
+    }
+
+    fn end_of_input_span(&self) -> Span {
+        match self.tokens.last() {
+            Some(token) => Span {
+                offset: token.span.offset + token.span.length,
+                column: token.span.column + token.span.length,
+                length: 0,
+                ..token.span
+            },
+            None => Span::new(0, 1, 1, 0),
+        }
+    }
+}
+
+// lookahead and backtracking for the parts of the C grammar that need them
+impl<T: Clone> Parser<T> {
+    /**
+     * Return a reference to the token `k` places after the next one without
+     * consuming anything. `peek_nth(0)` is `peek()`
+     */
+    // for casts and labelled statements, which the front end does not parse yet
+    #[allow(dead_code)]
+    pub fn peek_nth(&self, k: usize) -> Option<&T> {
+        self.tokens.get(self.cursor + k).map(|token| &token.node)
+    }
+
+    /**
+     * Number of tokens consumed so far
+     */
+    // only the tests look at it so far
+    #[allow(dead_code)]
+    pub fn position(&self) -> usize {
+        self.cursor
+    }
+
+    /**
+     * Saves the current position so that a speculative parse can be undone
+     * with `rewind`, e.g. to try a cast before a parenthesized expression
+     */
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.cursor)
+    }
+
+    /**
+     * Goes back to a position saved by `checkpoint`. Tokens consumed since
+     * then will be returned again
+     */
+    pub fn rewind(&mut self, checkpoint: Checkpoint) {
+        self.cursor = checkpoint.0;
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::c::lexer::Token;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Tok {
+        Open,
+        Close,
+        Number(u32),
+    }
+
+    fn spanned(tokens: Vec<Tok>) -> Vec<Spanned<Tok>> {
+        tokens
+            .into_iter()
+            .enumerate()
+            .map(|(i, token)| Spanned::new(token, Span::new(i, 1, i + 1, 1)))
+            .collect()
+    }
 
     #[test]
     #[should_panic = "custom expectation error message"]
     fn panic_on_no_token() {
-        let tokens: Vec<Token> = vec![];
+        let tokens: Vec<Spanned<Tok>> = vec![];
         let mut parser = Parser::new(tokens);
         parser.eat().expect("custom expectation error message");
     }
@@ -45,13 +164,13 @@ mod tests {
     #[test]
     fn eat_consumes_token_under_cursor() {
         let mut parser = Parser {
-            tokens: vec![Token::OpenParenthesis, Token::CloseParenthesis],
+            tokens: spanned(vec![Tok::Open, Tok::Close]),
             cursor: 0,
         };
         let first = parser.eat();
         let second = parser.eat();
-        assert_eq!(first, Some(Token::OpenParenthesis));
-        assert_eq!(second, Some(Token::CloseParenthesis));
+        assert_eq!(first, Some(Tok::Open));
+        assert_eq!(second, Some(Tok::Close));
 
         // now that we've consumed all tokens, next call should be None
         assert_eq!(parser.eat(), None);
@@ -60,7 +179,7 @@ mod tests {
     #[test]
     fn peek_does_not_consume_token() {
         let parser = Parser {
-            tokens: vec![Token::Semicolon],
+            tokens: spanned(vec![Tok::Number(1)]),
             cursor: 0,
         };
         let mut next_token = parser.peek();
@@ -68,7 +187,66 @@ mod tests {
             next_token = parser.peek();
         }
 
-        assert_eq!(next_token, Some(&Token::Semicolon));
+        assert_eq!(next_token, Some(&Tok::Number(1)));
         assert_eq!(parser.cursor, 0);
     }
+
+    #[test]
+    fn peek_nth_looks_ahead() {
+        let mut parser = Parser::new(spanned(vec![Tok::Open, Tok::Number(2), Tok::Close]));
+        assert_eq!(parser.peek_nth(0), parser.peek());
+        assert_eq!(parser.peek_nth(2), Some(&Tok::Close));
+        assert_eq!(parser.peek_nth(3), None);
+        parser.eat();
+        assert_eq!(parser.peek_nth(1), Some(&Tok::Close));
+        assert_eq!(parser.position(), 1);
+    }
+
+    #[test]
+    fn expect_consumes_only_matching_token() {
+        let mut parser = Parser::new(spanned(vec![Tok::Number(7), Tok::Open]));
+        let number = parser.expect(|tok| matches!(tok, Tok::Number(_)), "a number");
+        assert_eq!(number, Ok(Tok::Number(7)));
+
+        let error = parser.expect(|tok| *tok == Tok::Close, "')'");
+        assert_eq!(
+            error,
+            Err(ExpectError {
+                expected: String::from("')'"),
+                found: Some(Tok::Open),
+                span: Span::new(1, 1, 2, 1),
+            })
+        );
+        assert_eq!(parser.position(), 1);
+
+        parser.eat();
+        let error = parser.expect(|tok| *tok == Tok::Close, "')'").unwrap_err();
+        assert_eq!(error.found, None);
+        assert_eq!(error.span, Span::new(2, 1, 3, 0));
+    }
+
+    #[test]
+    fn rewind_returns_to_checkpoint() {
+        let mut parser = Parser::new(spanned(vec![Tok::Open, Tok::Number(1), Tok::Close]));
+        parser.eat();
+        let checkpoint = parser.checkpoint();
+        assert_eq!(parser.eat(), Some(Tok::Number(1)));
+        assert_eq!(parser.eat(), Some(Tok::Close));
+        parser.rewind(checkpoint);
+        assert_eq!(parser.position(), 1);
+        assert_eq!(parser.peek(), Some(&Tok::Number(1)));
+        assert_eq!(parser.last_span(), Span::new(0, 1, 1, 1));
+    }
+
+    #[test]
+    fn spans_follow_cursor() {
+        let mut parser = Parser::new(spanned(vec![Tok::Open, Tok::Close]));
+        assert_eq!(parser.peek_span(), Span::new(0, 1, 1, 1));
+        parser.eat();
+        assert_eq!(parser.last_span(), Span::new(0, 1, 1, 1));
+        assert_eq!(parser.peek_span(), Span::new(1, 1, 2, 1));
+        parser.eat();
+        // past the end we point just after the last token
+        assert_eq!(parser.peek_span(), Span::new(2, 1, 3, 0));
+    }
 }
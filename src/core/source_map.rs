@@ -0,0 +1,62 @@
+use super::span::{FileId, Span};
+
+/**
+ * Names of every file that contributed tokens to a compilation, so that spans
+ * can be reported as `file:line:column`
+ */
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    files: Vec<String>,
+}
+
+impl SourceMap {
+    pub fn new(main_file: &str) -> Self {
+        SourceMap {
+            files: vec![main_file.to_string()],
+        }
+    }
+
+    /**
+     * Registers a file, returning the id already assigned to it if it was
+     * seen before
+     */
+    pub fn add(&mut self, name: &str) -> FileId {
+        match self.files.iter().position(|file| file == name) {
+            Some(index) => FileId(index),
+            None => {
+                self.files.push(name.to_string());
+                FileId(self.files.len() - 1)
+            }
+        }
+    }
+
+    pub fn name(&self, file: FileId) -> &str {
+        self.files.get(file.0).map_or("<unknown>", String::as_str)
+    }
+
+    /**
+     * Renders where a span starts as `file:line:column`
+     */
+    pub fn location(&self, span: Span) -> String {
+        format!("{}:{span}", self.name(span.file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_are_registered_once() {
+        let mut source_map = SourceMap::new("main.c");
+        let header = source_map.add("foo.h");
+        assert_eq!(header, FileId(1));
+        assert_eq!(source_map.add("main.c"), FileId(0));
+        assert_eq!(source_map.add("foo.h"), header);
+        assert_eq!(source_map.name(header), "foo.h");
+        assert_eq!(
+            source_map.location(Span::new(0, 3, 7, 1)),
+            String::from("main.c:3:7")
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use std::fmt;
+
+/**
+ * Index of a file registered in a `SourceMap`. The main input file is always 0
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub struct FileId(pub usize);
+
+/**
+ * Location of a piece of source text. `offset` and `length` are measured in
+ * bytes from the start of the input, `line` and `column` are 1-based so they
+ * can be shown to users as-is
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub file: FileId,
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+}
+
+impl Span {
+    pub fn new(offset: usize, line: usize, column: usize, length: usize) -> Self {
+        Span {
+            file: FileId::default(),
+            offset,
+            line,
+            column,
+            length,
+        }
+    }
+
+    /**
+     * Returns a span starting where `self` starts and ending where `end` ends.
+     * Used to cover a whole AST node from its first to its last token
+     */
+    pub fn to(&self, end: Span) -> Span {
+        let end_offset = end.offset + end.length;
+        Span {
+            length: end_offset.saturating_sub(self.offset),
+            ..*self
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/**
+ * Pairs any value (usually a lexical token) with the span it was read from
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Self {
+        Spanned { node, span }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_covers_both_spans() {
+        let start = Span::new(4, 1, 5, 3);
+        let end = Span::new(12, 2, 3, 2);
+        assert_eq!(start.to(end), Span::new(4, 1, 5, 10));
+    }
+}
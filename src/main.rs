@@ -1,4 +1,4 @@
-use clap::Parser;
+use clap::{ArgGroup, CommandFactory, Parser, ValueEnum, error::ErrorKind};
 use regex::Regex;
 use std::{fs, process};
 
@@ -7,38 +7,117 @@ mod c;
 mod core;
 mod tacky;
 
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[cfg(feature = "dump-ast")]
+#[derive(Clone, Copy, ValueEnum)]
+enum DumpFormat {
+    Json,
+    Sexpr,
+}
+
 #[derive(Parser)]
+#[command(group(ArgGroup::new("stage").args(["parse", "validate", "tacky", "codegen"]).multiple(true)))]
 struct Cli {
     #[arg(short, long)]
     lex: bool,
     #[arg(short, long)]
     parse: bool,
+    /// Print the parsed program back as formatted C source
+    #[arg(long, requires = "parse")]
+    print: bool,
+    /// Stop after checking variable declarations and uses and labeling loops and switches
+    #[arg(short, long)]
+    validate: bool,
     #[arg(short, long)]
     tacky: bool,
     #[arg(short, long)]
     codegen: bool,
+    /// Add a directory to the #include search path
+    #[arg(short = 'I', value_name = "DIR")]
+    include_paths: Vec<String>,
+    /// Output format of --lex
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// Print the tree of the stage selected by --parse, --validate, --tacky or --codegen
+    #[cfg(feature = "dump-ast")]
+    #[arg(long, value_enum, requires = "stage", conflicts_with = "print")]
+    dump_ast: Option<DumpFormat>,
     filepath: String,
 }
 
+/**
+ * Prints `tree` and exits if --dump-ast was given
+ */
+#[cfg(feature = "dump-ast")]
+fn dump_ast(cli: &Cli, tree: &impl core::dump::Dump, source_map: &core::source_map::SourceMap) {
+    let Some(format) = cli.dump_ast else {
+        return;
+    };
+    match format {
+        DumpFormat::Json => println!("{}", core::dump::to_json(tree, source_map)),
+        DumpFormat::Sexpr => println!("{}", core::dump::to_sexpr(tree, source_map)),
+    }
+    process::exit(0);
+}
+
+#[cfg(not(feature = "dump-ast"))]
+fn dump_ast<T>(_cli: &Cli, _tree: &T, _source_map: &core::source_map::SourceMap) {}
+
 fn main() {
     let cli = Cli::parse();
+    if cli.format == Format::Json && !cli.lex {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--format=json is only supported with --lex",
+            )
+            .exit();
+    }
     let input_path = cli.filepath.as_str();
     let contents = fs::read_to_string(input_path)
-        .expect(&format!("Failed to read input file {:?}", input_path));
-    let c_program = c::process_program(contents, cli.lex);
+        .unwrap_or_else(|_| panic!("Failed to read input file {:?}", input_path));
+    let (tokens, source_map) = c::preprocess_program(contents, input_path, &cli.include_paths);
+    if cli.lex {
+        match cli.format {
+            Format::Text => print!("{}", c::lexer::tokens_to_text(&tokens, &source_map)),
+            Format::Json => print!("{}", c::lexer::tokens_to_json(&tokens, &source_map)),
+        }
+        process::exit(0);
+    }
+    let c_program = c::parse_program(tokens, &source_map);
     if cli.parse {
+        dump_ast(&cli, &c_program, &source_map);
+        match cli.print {
+            true => print!("{}", c::to_source::program_to_string(&c_program)),
+            false => println!("{c_program:#?}"),
+        }
+        process::exit(0);
+    }
+    let c_program = c::validate_program(c_program, &source_map);
+    if cli.validate {
+        dump_ast(&cli, &c_program, &source_map);
+        println!("{c_program:#?}");
         process::exit(0);
     }
     let tacky_program = c::to_tacky::translate_program(c_program);
     if cli.tacky {
+        dump_ast(&cli, &tacky_program, &source_map);
+        println!("{tacky_program:#?}");
         process::exit(0);
     }
     let asm_program = asm::tacky_program_to_asm_code(tacky_program);
     if cli.codegen {
+        dump_ast(&cli, &asm_program, &source_map);
+        print!("{}", asm::to_code::asm_program_to_string(asm_program));
         process::exit(0);
     }
     let asm_output = asm::to_code::asm_program_to_string(asm_program);
-    let output_path = Regex::new(r"\.i$")
+    let output_path = Regex::new(r"\.[ci]$")
         .unwrap()
         .replace(input_path, ".s")
         .to_string();
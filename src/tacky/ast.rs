@@ -34,7 +34,8 @@ pub enum Value {
      * Representing TACKY variables with a string + integer allows us to easily
      * expand nested operations (ex: `-(-2)`) into an inline series of
      * operations with each intermediate value stored in an intermediate
-     * variable
+     * variable. C variables have index 0 and every intermediate variable has
+     * an index of its own, so the two never clash
      */
     Variable(String, i32),
 }
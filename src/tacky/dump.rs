@@ -0,0 +1,126 @@
+use super::ast::*;
+use crate::core::dump::{Dump, Node};
+use crate::core::source_map::SourceMap;
+
+impl Dump for UnaryOperator {
+    fn dump(&self, _source_map: &SourceMap) -> Node {
+        Node::Unit(match self {
+            UnaryOperator::Complement => "Complement",
+            UnaryOperator::Negate => "Negate",
+            UnaryOperator::Not => "Not",
+        })
+    }
+}
+
+impl Dump for BinaryOperator {
+    fn dump(&self, _source_map: &SourceMap) -> Node {
+        Node::Unit(match self {
+            BinaryOperator::Add => "Add",
+            BinaryOperator::Subtract => "Subtract",
+            BinaryOperator::Multiply => "Multiply",
+            BinaryOperator::Divide => "Divide",
+            BinaryOperator::Modulo => "Modulo",
+            BinaryOperator::LeftShift => "LeftShift",
+            BinaryOperator::RightShift => "RightShift",
+            BinaryOperator::BitwiseAnd => "BitwiseAnd",
+            BinaryOperator::BitwiseOr => "BitwiseOr",
+            BinaryOperator::BitwiseXor => "BitwiseXor",
+            BinaryOperator::LogicalAnd => "LogicalAnd",
+            BinaryOperator::LogicalOr => "LogicalOr",
+            BinaryOperator::Equal => "Equal",
+            BinaryOperator::NotEqual => "NotEqual",
+            BinaryOperator::LessThan => "LessThan",
+            BinaryOperator::LessThanEqual => "LessThanEqual",
+            BinaryOperator::GreaterThan => "GreaterThan",
+            BinaryOperator::GreaterThanEqual => "GreaterThanEqual",
+        })
+    }
+}
+
+impl Dump for Value {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        match self {
+            Value::Constant(value) => {
+                Node::Variant("Constant", vec![("value", value.dump(source_map))])
+            }
+            Value::Variable(name, index) => Node::Variant(
+                "Variable",
+                vec![
+                    ("name", name.dump(source_map)),
+                    ("index", index.dump(source_map)),
+                ],
+            ),
+        }
+    }
+}
+
+impl Dump for Instruction {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        match self {
+            Instruction::Return(value) => {
+                Node::Variant("Return", vec![("value", value.dump(source_map))])
+            }
+            Instruction::Unary(operator, src, dst) => Node::Variant(
+                "Unary",
+                vec![
+                    ("operator", operator.dump(source_map)),
+                    ("src", src.dump(source_map)),
+                    ("dst", dst.dump(source_map)),
+                ],
+            ),
+            Instruction::Binary(operator, left, right, dst) => Node::Variant(
+                "Binary",
+                vec![
+                    ("operator", operator.dump(source_map)),
+                    ("left", left.dump(source_map)),
+                    ("right", right.dump(source_map)),
+                    ("dst", dst.dump(source_map)),
+                ],
+            ),
+            Instruction::Copy(src, dst) => Node::Variant(
+                "Copy",
+                vec![("src", src.dump(source_map)), ("dst", dst.dump(source_map))],
+            ),
+            Instruction::Jump(target) => {
+                Node::Variant("Jump", vec![("target", target.dump(source_map))])
+            }
+            Instruction::JumpIfZero(condition, target) => Node::Variant(
+                "JumpIfZero",
+                vec![
+                    ("condition", condition.dump(source_map)),
+                    ("target", target.dump(source_map)),
+                ],
+            ),
+            Instruction::JumpIfNotZero(condition, target) => Node::Variant(
+                "JumpIfNotZero",
+                vec![
+                    ("condition", condition.dump(source_map)),
+                    ("target", target.dump(source_map)),
+                ],
+            ),
+            Instruction::Label(name) => {
+                Node::Variant("Label", vec![("name", name.dump(source_map))])
+            }
+        }
+    }
+}
+
+impl Dump for Function {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        let Function::Function(name, instructions) = self;
+        Node::Variant(
+            "Function",
+            vec![
+                ("name", name.dump(source_map)),
+                ("instructions", instructions.dump(source_map)),
+            ],
+        )
+    }
+}
+
+impl Dump for Program {
+    fn dump(&self, source_map: &SourceMap) -> Node {
+        let Program::Program(function) = self;
+        Node::Variant("Program", vec![("function", function.dump(source_map))])
+    }
+}
@@ -1 +1,3 @@
 pub mod ast;
+#[cfg(feature = "dump-ast")]
+mod dump;
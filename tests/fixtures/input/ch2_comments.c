@@ -0,0 +1,8 @@
+/* Block comment before the function
+ * spanning several lines */
+int main(void) {
+    // a line comment \
+       continued through a splice
+    return ~\
+(-2); /* trailing */
+}
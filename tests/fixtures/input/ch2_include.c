@@ -0,0 +1,15 @@
+#include "include/constants.h"
+#include "include/constants.h"
+#include <compare.h>
+
+#ifndef THREE
+#error THREE is missing
+#elif THREE > 2
+int main(void) {
+    return SHIFT(THREE) - ~TWO;
+}
+#else
+int main(void) {
+    return 0;
+}
+#endif
@@ -0,0 +1,9 @@
+# 0 "main.c"
+# 0 "<built-in>"
+# 0 "<command-line>"
+# 1 "main.c"
+# 1 "foo.h" 1
+int main(void) {
+    return ~;
+# 2 "main.c" 2
+}
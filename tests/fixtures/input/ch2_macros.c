@@ -0,0 +1,7 @@
+#define NEG(x) -(x)
+#define COMPLEMENT(x) ~x
+#define TWO 2
+
+int main(void) {
+    return COMPLEMENT(NEG(TWO));
+}
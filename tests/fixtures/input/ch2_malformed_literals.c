@@ -0,0 +1,5 @@
+int main(void) {
+    int a = 08;
+    int b = 1.2.3;
+    return 0x + a;
+}
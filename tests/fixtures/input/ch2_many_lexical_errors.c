@@ -0,0 +1,4 @@
+int main(void) {
+    int 1x = @;
+    return a ` $ 2;
+}
@@ -0,0 +1,5 @@
+#include "include/broken.h"
+#frobnicate
+int main(void) {
+    return 0;
+}
@@ -0,0 +1,8 @@
+int main(void) {
+    int a;
+    int b = 2;
+    a = b = b * 3;
+    int c = a && b;
+    c = c || a;
+    return a + b + c;
+}
@@ -0,0 +1,4 @@
+int main(void) {
+    int a = 1;
+    a = a + 1;
+}
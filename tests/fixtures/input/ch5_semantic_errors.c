@@ -0,0 +1,7 @@
+int main(void) {
+    int a = 1;
+    int a;
+    b = a;
+    a + 1 = 2;
+    return a;
+}
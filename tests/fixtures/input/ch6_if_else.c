@@ -0,0 +1,17 @@
+int main(void) {
+    int a = 0;
+    int b = 5;
+    if (a)
+        if (b)
+            return 1;
+        else
+            a = b > 3 ? 10 : 20;
+    else if (b == 5)
+        a = b ? a ? 1 : 2 : 3;
+    else
+        a = 99;
+    int c = a == 2 ? b < 0 ? 7 : 8 : 9;
+    if (c == 8)
+        c = c + (a ? 100 : 200);
+    return a + c;
+}
@@ -0,0 +1,24 @@
+int main(void) {
+    int x = 1;
+    {
+        int x = 2;
+        {
+            x = x + 10;
+            int x = 3;
+            x = x * 2;
+        }
+        if (x == 12) {
+            int y = x;
+            x = y + 1;
+        } else
+            x = 0;
+    }
+    if (x) {
+    } else {
+        return 100;
+    }
+    if (x) if (0) x = 5; else {}
+    if (x) { if (0) x = 7; } else x = 9;
+    {}
+    return x;
+}
@@ -0,0 +1,9 @@
+int main(void) {
+    int a = 1;
+    if (a)
+        break;
+    while (a) {
+        a = 0;
+    }
+    continue;
+}
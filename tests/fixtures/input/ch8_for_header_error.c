@@ -0,0 +1,9 @@
+int main(void) {
+    int a = 0;
+    for (int i = 0, j; ;)
+        a = a + 1;
+    for (a = 0; a < 3; a = a + 1) {
+        a = a * 2;
+    }
+    return a;
+}
@@ -0,0 +1,26 @@
+int main(void) {
+    int total = 0;
+    for (int i = 0; i < 10; i = i + 1) {
+        if (i % 2)
+            continue;
+        total = total + i;
+    }
+    int i = 0;
+    while (1) {
+        i = i + 1;
+        if (i > 5)
+            break;
+    }
+    do i = i - 2; while (i > 0);
+    int n = 0;
+    for (;;) {
+        n = n + 1;
+        for (int j = 0; j < 3; j = j + 1)
+            if (j == 1) break;
+        if (n == 4) break;
+    }
+    for (i = 0; i < 3;)
+        i = i + 1;
+    for (int i = 100; i; i = 0) total = total + i;
+    return total + i + n;
+}
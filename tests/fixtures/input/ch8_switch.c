@@ -0,0 +1,22 @@
+int main(void) {
+    int total = 0;
+    for (int i = 0; i < 6; i = i + 1) {
+        switch (i % 4) {
+            case 0:
+                total = total + 1;
+            case 1:
+                total = total + 10;
+                break;
+            case 'a' - 'b':
+                total = 0;
+            default:
+                if (i == 5)
+                    continue;
+                total = total * 2;
+        }
+    }
+    switch (total)
+        case 0:
+            return 1;
+    return total;
+}
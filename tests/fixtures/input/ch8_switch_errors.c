@@ -0,0 +1,14 @@
+int main(void) {
+    int a = 1;
+    case 1:
+    switch (a) {
+        case a:
+            break;
+        case 2:
+        case 1 + 1:
+            a = 3;
+        default:
+        default:
+            return a;
+    }
+}
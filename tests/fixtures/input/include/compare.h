@@ -0,0 +1,5 @@
+#if INT_BITS == 32 && defined(THREE)
+#define SHIFT(x) ((x) << TWO)
+#else
+#error wrong INT_BITS
+#endif
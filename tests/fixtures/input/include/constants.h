@@ -0,0 +1,9 @@
+#ifndef CONSTANTS_H
+#define CONSTANTS_H
+
+#include "sys/limits.h"
+
+#define TWO 2
+#define THREE (TWO + 1)
+
+#endif
@@ -0,0 +1,3 @@
+#pragma once
+
+#define INT_BITS 32
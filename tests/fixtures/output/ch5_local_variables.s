@@ -0,0 +1,51 @@
+  .globl main
+main:
+  pushq %rbp
+  movq %rsp, %rbp
+  subq $32, %rsp
+  movl $2, -4(%rbp)
+  movl -4(%rbp), %r10d
+  movl %r10d, -8(%rbp)
+  movl -8(%rbp), %r11d
+  imull $3, %r11d
+  movl %r11d, -8(%rbp)
+  movl -8(%rbp), %r10d
+  movl %r10d, -4(%rbp)
+  movl -4(%rbp), %r10d
+  movl %r10d, -12(%rbp)
+  cmpl $0, -12(%rbp)
+  je .LLogicalAndOfa.0Andb.1.2_FALSE
+  cmpl $0, -4(%rbp)
+  je .LLogicalAndOfa.0Andb.1.2_FALSE
+  movl $1, -16(%rbp)
+  jmp .LLogicalAndOfa.0Andb.1.2_END
+.LLogicalAndOfa.0Andb.1.2_FALSE:
+  movl $0, -16(%rbp)
+.LLogicalAndOfa.0Andb.1.2_END:
+  movl -16(%rbp), %r10d
+  movl %r10d, -20(%rbp)
+  cmpl $0, -20(%rbp)
+  jne .LLogicalOrOfc.2Anda.0.3_TRUE
+  cmpl $0, -12(%rbp)
+  jne .LLogicalOrOfc.2Anda.0.3_TRUE
+  movl $0, -24(%rbp)
+  jmp .LLogicalOrOfc.2Anda.0.3_END
+.LLogicalOrOfc.2Anda.0.3_TRUE:
+  movl $1, -24(%rbp)
+.LLogicalOrOfc.2Anda.0.3_END:
+  movl -24(%rbp), %r10d
+  movl %r10d, -20(%rbp)
+  movl -12(%rbp), %r10d
+  movl %r10d, -28(%rbp)
+  movl -4(%rbp), %r10d
+  addl %r10d, -28(%rbp)
+  movl -28(%rbp), %r10d
+  movl %r10d, -32(%rbp)
+  movl -20(%rbp), %r10d
+  addl %r10d, -32(%rbp)
+  movl -32(%rbp), %eax
+  movq %rbp, %rsp
+  popq %rbp
+  ret
+
+.section .note.GNU-stack,"",@progbits
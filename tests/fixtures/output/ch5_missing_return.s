@@ -0,0 +1,17 @@
+  .globl main
+main:
+  pushq %rbp
+  movq %rsp, %rbp
+  subq $8, %rsp
+  movl $1, -4(%rbp)
+  movl -4(%rbp), %r10d
+  movl %r10d, -8(%rbp)
+  addl $1, -8(%rbp)
+  movl -8(%rbp), %r10d
+  movl %r10d, -4(%rbp)
+  movl $0, %eax
+  movq %rbp, %rsp
+  popq %rbp
+  ret
+
+.section .note.GNU-stack,"",@progbits
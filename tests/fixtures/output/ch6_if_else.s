@@ -0,0 +1,111 @@
+  .globl main
+main:
+  pushq %rbp
+  movq %rsp, %rbp
+  subq $64, %rsp
+  movl $0, -4(%rbp)
+  movl $5, -8(%rbp)
+  cmpl $0, -4(%rbp)
+  je .LIf.1_ELSE
+  cmpl $0, -8(%rbp)
+  je .LIf.2_ELSE
+  movl $1, %eax
+  movq %rbp, %rsp
+  popq %rbp
+  ret
+  jmp .LIf.2_END
+.LIf.2_ELSE:
+  cmpl $3, -8(%rbp)
+  movl $0, -12(%rbp)
+  setg -12(%rbp)
+  cmpl $0, -12(%rbp)
+  je .LConditional.4_ELSE
+  movl $10, -16(%rbp)
+  jmp .LConditional.4_END
+.LConditional.4_ELSE:
+  movl $20, -16(%rbp)
+.LConditional.4_END:
+  movl -16(%rbp), %r10d
+  movl %r10d, -4(%rbp)
+.LIf.2_END:
+  jmp .LIf.1_END
+.LIf.1_ELSE:
+  cmpl $5, -8(%rbp)
+  movl $0, -20(%rbp)
+  sete -20(%rbp)
+  cmpl $0, -20(%rbp)
+  je .LIf.5_ELSE
+  cmpl $0, -8(%rbp)
+  je .LConditional.8_ELSE
+  cmpl $0, -4(%rbp)
+  je .LConditional.7_ELSE
+  movl $1, -24(%rbp)
+  jmp .LConditional.7_END
+.LConditional.7_ELSE:
+  movl $2, -24(%rbp)
+.LConditional.7_END:
+  movl -24(%rbp), %r10d
+  movl %r10d, -28(%rbp)
+  jmp .LConditional.8_END
+.LConditional.8_ELSE:
+  movl $3, -28(%rbp)
+.LConditional.8_END:
+  movl -28(%rbp), %r10d
+  movl %r10d, -4(%rbp)
+  jmp .LIf.5_END
+.LIf.5_ELSE:
+  movl $99, -4(%rbp)
+.LIf.5_END:
+.LIf.1_END:
+  cmpl $2, -4(%rbp)
+  movl $0, -32(%rbp)
+  sete -32(%rbp)
+  cmpl $0, -32(%rbp)
+  je .LConditional.12_ELSE
+  cmpl $0, -8(%rbp)
+  movl $0, -36(%rbp)
+  setl -36(%rbp)
+  cmpl $0, -36(%rbp)
+  je .LConditional.11_ELSE
+  movl $7, -40(%rbp)
+  jmp .LConditional.11_END
+.LConditional.11_ELSE:
+  movl $8, -40(%rbp)
+.LConditional.11_END:
+  movl -40(%rbp), %r10d
+  movl %r10d, -44(%rbp)
+  jmp .LConditional.12_END
+.LConditional.12_ELSE:
+  movl $9, -44(%rbp)
+.LConditional.12_END:
+  movl -44(%rbp), %r10d
+  movl %r10d, -48(%rbp)
+  cmpl $8, -48(%rbp)
+  movl $0, -52(%rbp)
+  sete -52(%rbp)
+  cmpl $0, -52(%rbp)
+  je .LIf.13_END
+  cmpl $0, -4(%rbp)
+  je .LConditional.15_ELSE
+  movl $100, -56(%rbp)
+  jmp .LConditional.15_END
+.LConditional.15_ELSE:
+  movl $200, -56(%rbp)
+.LConditional.15_END:
+  movl -48(%rbp), %r10d
+  movl %r10d, -60(%rbp)
+  movl -56(%rbp), %r10d
+  addl %r10d, -60(%rbp)
+  movl -60(%rbp), %r10d
+  movl %r10d, -48(%rbp)
+.LIf.13_END:
+  movl -4(%rbp), %r10d
+  movl %r10d, -64(%rbp)
+  movl -48(%rbp), %r10d
+  addl %r10d, -64(%rbp)
+  movl -64(%rbp), %eax
+  movq %rbp, %rsp
+  popq %rbp
+  ret
+
+.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,72 @@
+  .globl main
+main:
+  pushq %rbp
+  movq %rsp, %rbp
+  subq $32, %rsp
+  movl $1, -4(%rbp)
+  movl $2, -8(%rbp)
+  movl -8(%rbp), %r10d
+  movl %r10d, -12(%rbp)
+  addl $10, -12(%rbp)
+  movl -12(%rbp), %r10d
+  movl %r10d, -8(%rbp)
+  movl $3, -16(%rbp)
+  movl -16(%rbp), %r10d
+  movl %r10d, -20(%rbp)
+  movl -20(%rbp), %r11d
+  imull $2, %r11d
+  movl %r11d, -20(%rbp)
+  movl -20(%rbp), %r10d
+  movl %r10d, -16(%rbp)
+  cmpl $12, -8(%rbp)
+  movl $0, -24(%rbp)
+  sete -24(%rbp)
+  cmpl $0, -24(%rbp)
+  je .LIf.3_ELSE
+  movl -8(%rbp), %r10d
+  movl %r10d, -28(%rbp)
+  movl -28(%rbp), %r10d
+  movl %r10d, -32(%rbp)
+  addl $1, -32(%rbp)
+  movl -32(%rbp), %r10d
+  movl %r10d, -8(%rbp)
+  jmp .LIf.3_END
+.LIf.3_ELSE:
+  movl $0, -8(%rbp)
+.LIf.3_END:
+  cmpl $0, -4(%rbp)
+  je .LIf.6_ELSE
+  jmp .LIf.6_END
+.LIf.6_ELSE:
+  movl $100, %eax
+  movq %rbp, %rsp
+  popq %rbp
+  ret
+.LIf.6_END:
+  cmpl $0, -4(%rbp)
+  je .LIf.7_END
+  movl $0, %r11d
+  cmpl $0, %r11d
+  je .LIf.8_ELSE
+  movl $5, -4(%rbp)
+  jmp .LIf.8_END
+.LIf.8_ELSE:
+.LIf.8_END:
+.LIf.7_END:
+  cmpl $0, -4(%rbp)
+  je .LIf.9_ELSE
+  movl $0, %r11d
+  cmpl $0, %r11d
+  je .LIf.10_END
+  movl $7, -4(%rbp)
+.LIf.10_END:
+  jmp .LIf.9_END
+.LIf.9_ELSE:
+  movl $9, -4(%rbp)
+.LIf.9_END:
+  movl -4(%rbp), %eax
+  movq %rbp, %rsp
+  popq %rbp
+  ret
+
+.section .note.GNU-stack,"",@progbits
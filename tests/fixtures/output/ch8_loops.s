@@ -0,0 +1,150 @@
+  .globl main
+main:
+  pushq %rbp
+  movq %rsp, %rbp
+  subq $96, %rsp
+  movl $0, -4(%rbp)
+  movl $0, -8(%rbp)
+.Lloop.0_START:
+  cmpl $10, -8(%rbp)
+  movl $0, -12(%rbp)
+  setl -12(%rbp)
+  cmpl $0, -12(%rbp)
+  je .Lloop.0_BREAK
+  movl -8(%rbp), %eax
+  cdq
+  movl $2, %r10d
+  idivl %r10d
+  movl %edx, -16(%rbp)
+  cmpl $0, -16(%rbp)
+  je .LIf.2_END
+  jmp .Lloop.0_CONTINUE
+.LIf.2_END:
+  movl -4(%rbp), %r10d
+  movl %r10d, -20(%rbp)
+  movl -8(%rbp), %r10d
+  addl %r10d, -20(%rbp)
+  movl -20(%rbp), %r10d
+  movl %r10d, -4(%rbp)
+.Lloop.0_CONTINUE:
+  movl -8(%rbp), %r10d
+  movl %r10d, -24(%rbp)
+  addl $1, -24(%rbp)
+  movl -24(%rbp), %r10d
+  movl %r10d, -8(%rbp)
+  jmp .Lloop.0_START
+.Lloop.0_BREAK:
+  movl $0, -28(%rbp)
+.Lloop.1_CONTINUE:
+  movl $1, %r11d
+  cmpl $0, %r11d
+  je .Lloop.1_BREAK
+  movl -28(%rbp), %r10d
+  movl %r10d, -32(%rbp)
+  addl $1, -32(%rbp)
+  movl -32(%rbp), %r10d
+  movl %r10d, -28(%rbp)
+  cmpl $5, -28(%rbp)
+  movl $0, -36(%rbp)
+  setg -36(%rbp)
+  cmpl $0, -36(%rbp)
+  je .LIf.7_END
+  jmp .Lloop.1_BREAK
+.LIf.7_END:
+  jmp .Lloop.1_CONTINUE
+.Lloop.1_BREAK:
+.Lloop.2_START:
+  movl -28(%rbp), %r10d
+  movl %r10d, -40(%rbp)
+  subl $2, -40(%rbp)
+  movl -40(%rbp), %r10d
+  movl %r10d, -28(%rbp)
+.Lloop.2_CONTINUE:
+  cmpl $0, -28(%rbp)
+  movl $0, -44(%rbp)
+  setg -44(%rbp)
+  cmpl $0, -44(%rbp)
+  jne .Lloop.2_START
+.Lloop.2_BREAK:
+  movl $0, -48(%rbp)
+.Lloop.3_START:
+  movl -48(%rbp), %r10d
+  movl %r10d, -52(%rbp)
+  addl $1, -52(%rbp)
+  movl -52(%rbp), %r10d
+  movl %r10d, -48(%rbp)
+  movl $0, -56(%rbp)
+.Lloop.4_START:
+  cmpl $3, -56(%rbp)
+  movl $0, -60(%rbp)
+  setl -60(%rbp)
+  cmpl $0, -60(%rbp)
+  je .Lloop.4_BREAK
+  cmpl $1, -56(%rbp)
+  movl $0, -64(%rbp)
+  sete -64(%rbp)
+  cmpl $0, -64(%rbp)
+  je .LIf.13_END
+  jmp .Lloop.4_BREAK
+.LIf.13_END:
+.Lloop.4_CONTINUE:
+  movl -56(%rbp), %r10d
+  movl %r10d, -68(%rbp)
+  addl $1, -68(%rbp)
+  movl -68(%rbp), %r10d
+  movl %r10d, -56(%rbp)
+  jmp .Lloop.4_START
+.Lloop.4_BREAK:
+  cmpl $4, -48(%rbp)
+  movl $0, -72(%rbp)
+  sete -72(%rbp)
+  cmpl $0, -72(%rbp)
+  je .LIf.16_END
+  jmp .Lloop.3_BREAK
+.LIf.16_END:
+.Lloop.3_CONTINUE:
+  jmp .Lloop.3_START
+.Lloop.3_BREAK:
+  movl $0, -28(%rbp)
+.Lloop.5_START:
+  cmpl $3, -28(%rbp)
+  movl $0, -76(%rbp)
+  setl -76(%rbp)
+  cmpl $0, -76(%rbp)
+  je .Lloop.5_BREAK
+  movl -28(%rbp), %r10d
+  movl %r10d, -80(%rbp)
+  addl $1, -80(%rbp)
+  movl -80(%rbp), %r10d
+  movl %r10d, -28(%rbp)
+.Lloop.5_CONTINUE:
+  jmp .Lloop.5_START
+.Lloop.5_BREAK:
+  movl $100, -84(%rbp)
+.Lloop.6_START:
+  cmpl $0, -84(%rbp)
+  je .Lloop.6_BREAK
+  movl -4(%rbp), %r10d
+  movl %r10d, -88(%rbp)
+  movl -84(%rbp), %r10d
+  addl %r10d, -88(%rbp)
+  movl -88(%rbp), %r10d
+  movl %r10d, -4(%rbp)
+.Lloop.6_CONTINUE:
+  movl $0, -84(%rbp)
+  jmp .Lloop.6_START
+.Lloop.6_BREAK:
+  movl -4(%rbp), %r10d
+  movl %r10d, -92(%rbp)
+  movl -28(%rbp), %r10d
+  addl %r10d, -92(%rbp)
+  movl -92(%rbp), %r10d
+  movl %r10d, -96(%rbp)
+  movl -48(%rbp), %r10d
+  addl %r10d, -96(%rbp)
+  movl -96(%rbp), %eax
+  movq %rbp, %rsp
+  popq %rbp
+  ret
+
+.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,91 @@
+  .globl main
+main:
+  pushq %rbp
+  movq %rsp, %rbp
+  subq $52, %rsp
+  movl $0, -4(%rbp)
+  movl $0, -8(%rbp)
+.Lloop.0_START:
+  cmpl $6, -8(%rbp)
+  movl $0, -12(%rbp)
+  setl -12(%rbp)
+  cmpl $0, -12(%rbp)
+  je .Lloop.0_BREAK
+  movl -8(%rbp), %eax
+  cdq
+  movl $4, %r10d
+  idivl %r10d
+  movl %edx, -16(%rbp)
+  cmpl $0, -16(%rbp)
+  movl $0, -20(%rbp)
+  sete -20(%rbp)
+  cmpl $0, -20(%rbp)
+  jne .Lswitch.1_CASE0
+  cmpl $1, -16(%rbp)
+  movl $0, -24(%rbp)
+  sete -24(%rbp)
+  cmpl $0, -24(%rbp)
+  jne .Lswitch.1_CASE1
+  cmpl $-1, -16(%rbp)
+  movl $0, -28(%rbp)
+  sete -28(%rbp)
+  cmpl $0, -28(%rbp)
+  jne .Lswitch.1_CASE2
+  jmp .Lswitch.1_DEFAULT
+.Lswitch.1_CASE0:
+  movl -4(%rbp), %r10d
+  movl %r10d, -32(%rbp)
+  addl $1, -32(%rbp)
+  movl -32(%rbp), %r10d
+  movl %r10d, -4(%rbp)
+.Lswitch.1_CASE1:
+  movl -4(%rbp), %r10d
+  movl %r10d, -36(%rbp)
+  addl $10, -36(%rbp)
+  movl -36(%rbp), %r10d
+  movl %r10d, -4(%rbp)
+  jmp .Lswitch.1_BREAK
+.Lswitch.1_CASE2:
+  movl $0, -4(%rbp)
+.Lswitch.1_DEFAULT:
+  cmpl $5, -8(%rbp)
+  movl $0, -40(%rbp)
+  sete -40(%rbp)
+  cmpl $0, -40(%rbp)
+  je .LIf.8_END
+  jmp .Lloop.0_CONTINUE
+.LIf.8_END:
+  movl -4(%rbp), %r10d
+  movl %r10d, -44(%rbp)
+  movl -44(%rbp), %r11d
+  imull $2, %r11d
+  movl %r11d, -44(%rbp)
+  movl -44(%rbp), %r10d
+  movl %r10d, -4(%rbp)
+.Lswitch.1_BREAK:
+.Lloop.0_CONTINUE:
+  movl -8(%rbp), %r10d
+  movl %r10d, -48(%rbp)
+  addl $1, -48(%rbp)
+  movl -48(%rbp), %r10d
+  movl %r10d, -8(%rbp)
+  jmp .Lloop.0_START
+.Lloop.0_BREAK:
+  cmpl $0, -4(%rbp)
+  movl $0, -52(%rbp)
+  sete -52(%rbp)
+  cmpl $0, -52(%rbp)
+  jne .Lswitch.2_CASE0
+  jmp .Lswitch.2_BREAK
+.Lswitch.2_CASE0:
+  movl $1, %eax
+  movq %rbp, %rsp
+  popq %rbp
+  ret
+.Lswitch.2_BREAK:
+  movl -4(%rbp), %eax
+  movq %rbp, %rsp
+  popq %rbp
+  ret
+
+.section .note.GNU-stack,"",@progbits